@@ -0,0 +1,57 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use rendererthingy::renderer::{
+    render_frame,
+    Camera,
+    Object,
+    Transform,
+    common::{Light, LightKind, Point3D, ShaderSettings},
+    model::Model,
+    normal_drawable::NormalDrawable,
+    buffer_display::BufferDisplay
+};
+
+
+const CUBE: &str = "v -1 -1 -1
+v 1 -1 -1
+v 1 1 -1
+v -1 1 -1
+v -1 -1 1
+v 1 -1 1
+v 1 1 1
+v -1 1 1
+f 1 3 2
+f 1 4 3
+f 5 6 7
+f 5 7 8
+f 1 2 6
+f 1 6 5
+f 4 8 7
+f 4 7 3
+f 1 5 8
+f 1 8 4
+f 2 3 7
+f 2 7 6
+";
+
+fn render_cube(c: &mut Criterion)
+{
+    let model = Model::read_obj_str(CUBE).unwrap();
+
+    let camera = Camera::new(0.1, 100.0, 90.0_f64.to_radians(), 1.0);
+    let lights = [Light{position: Point3D{x: 2.0, y: 3.0, z: 1.0}, intensity: 1.0, kind: LightKind::Point}];
+    let settings = ShaderSettings::default();
+
+    let transform = Transform::new((0.0, 0.0, -4.0), (1.0, 1.0, 1.0), 0.5, (0.3, 1.0, 0.0));
+    let object = Object::new(&model, transform, &camera, &lights, &settings);
+
+    let mut drawable = NormalDrawable::new((256, 256), BufferDisplay::new());
+
+    c.bench_function("render cube 256x256", |b|
+    {
+        b.iter(|| render_frame(&object, &mut drawable))
+    });
+}
+
+criterion_group!(benches, render_cube);
+criterion_main!(benches);
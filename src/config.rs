@@ -1,6 +1,14 @@
 use std::{
     env,
-    process
+    process,
+    path::Path
+};
+
+use rendererthingy::renderer::{
+    common::{Color, Point3D, Light, LightKind, Visualization, Tonemap, Fog, Outline, Gamma, Region},
+    bloom_display::Bloom,
+    console_screen::{DEFAULT_CHARSET, ColorMode},
+    normal_drawable::drawable::Rasterizer
 };
 
 pub enum DrawMode
@@ -9,6 +17,20 @@ pub enum DrawMode
     Console
 }
 
+pub enum OutputFormat
+{
+    Image,
+    Ppm,
+    Exr
+}
+
+pub enum Winding
+{
+    CounterClockwise,
+    Clockwise,
+    Auto
+}
+
 pub enum ConfigError
 {
     ParseError(String),
@@ -20,31 +42,151 @@ pub enum ConfigError
 
 pub struct Config
 {
-    pub model_path: String,
+    pub model_path: Option<String>,
+    pub scene_path: Option<String>,
+    //extra models placed at positions in world space
+    pub models: Vec<(String, Point3D)>,
     pub filename: String,
+    pub format: OutputFormat,
     pub draw_mode: DrawMode,
     pub size: Option<(usize, usize)>,
     pub distance: f64,
+    pub fit: bool,
+    pub center: bool,
+    pub normalize: bool,
+    pub weld: Option<f64>,
+    pub floor: Option<f64>,
+    pub translate: (f64, f64, f64),
+    pub fov: f64,
+    pub roll: Option<f64>,
     pub rotation: f64,
-    pub undeferred: bool
+    pub axis: (f64, f64, f64),
+    pub rotation_euler: Option<(f64, f64, f64)>,
+    pub lights: Vec<Light>,
+    pub ambient: f64,
+    pub specular: f64,
+    pub diffuse: f64,
+    pub energy_conserving: bool,
+    pub cel_bands: Option<usize>,
+    pub winding: Winding,
+    pub outline: Option<Outline>,
+    pub frames: Option<usize>,
+    pub sequence: Option<String>,
+    pub visualization: Option<Visualization>,
+    pub tonemap: Option<Tonemap>,
+    pub fog: Option<Fog>,
+    pub focus: Option<(f64, f64)>,
+    pub bloom: Option<Bloom>,
+    pub skybox: Option<String>,
+    pub matcap: Option<String>,
+    pub reflectivity: Option<f64>,
+    pub undeferred: bool,
+    pub rasterizer: Rasterizer,
+    pub preview: bool,
+    pub supersample: Option<usize>,
+    pub region: Option<Region>,
+    pub crop: bool,
+    pub oit: bool,
+    pub no_cull: bool,
+    pub min_triangle_area: f64,
+    pub wireframe: bool,
+    pub gizmo: bool,
+    pub bounding_box: bool,
+    pub smooth_lines: bool,
+    pub double_sided: bool,
+    pub zprepass: bool,
+    pub transparent: bool,
+    pub gamma: Gamma,
+    pub truecolor: bool,
+    pub console_color: Option<ColorMode>,
+    pub charset: String,
+    pub inverted: bool,
+    pub dither: bool,
+    pub char_aspect: f64,
+    pub match_aspect: Option<f64>,
+    pub fps: f64,
+    pub rotation_speed: f64,
+    pub stats: bool,
+    pub progress: bool
 }
 
 impl Config
 {
+    #[allow(clippy::suspicious_else_formatting)]
     pub fn parse<T: Iterator<Item=String>>(args: T) -> Result<Self, ConfigError>
     {
         let mut model_path = None;
-        let mut filename = "output.png".to_owned();
+        let mut scene_path = None;
+        let mut models = Vec::new();
+        let mut filename = None;
+        let mut format = OutputFormat::Image;
         let mut draw_mode = DrawMode::Picture;
         let mut size = None;
         let mut distance = 50.0;
+        let mut fit = false;
+        let mut center = false;
+        let mut normalize = false;
+        let mut weld = None;
+        let mut floor = None;
+        let mut translate = (0.0, 0.0, 0.0);
+        let mut fov = 60.0;
+        let mut roll = None;
         let mut rotation = 0.9;
+        let mut axis = (0.2, 0.3, 0.4);
+        let mut rotation_euler = None;
+        let mut lights = Vec::new();
+        let mut ambient = 0.2;
+        let mut specular = 1.0;
+        let mut diffuse = 1.0;
+        let mut energy_conserving = false;
+        let mut cel_bands = None;
+        let mut winding = Winding::CounterClockwise;
+        let mut outline = None;
+        let mut outline_object = None;
+        let mut frames = None;
+        let mut sequence = None;
+        let mut visualization = None;
+        let mut tonemap = None;
+        let mut fog = None;
+        let mut focus = None;
+        let mut bloom = None;
+        let mut skybox = None;
+        let mut matcap = None;
+        let mut reflectivity = None;
         let mut undeferred = false;
+        let mut rasterizer = Rasterizer::Barycentric;
+        let mut preview = false;
+        let mut supersample = None;
+        let mut region = None;
+        let mut crop = false;
+        let mut oit = false;
+        let mut no_cull = false;
+        let mut min_triangle_area = 0.0;
+        let mut wireframe = false;
+        let mut gizmo = false;
+        let mut bounding_box = false;
+        let mut smooth_lines = false;
+        let mut double_sided = false;
+        let mut zprepass = false;
+        let mut transparent = false;
+        let mut gamma = Gamma::Srgb;
+        let mut truecolor = false;
+        let mut console_color = None;
+        let mut charset = DEFAULT_CHARSET.to_owned();
+        let mut inverted = false;
+        let mut dither = false;
+        let mut char_aspect = 0.5;
+        let mut match_aspect = None;
+        let mut fps: f64 = 10.0;
+        let mut rotation_speed: f64 = 2.5;
+        let mut stats = false;
+        let mut progress = false;
 
         let mut args = args.peekable();
         while let Some(arg) = args.next()
         {
-            if args.peek().is_none()
+            //a scene file replaces the model path so the last argument can be a flag
+            if args.peek().is_none() && (arg == "-" || !arg.starts_with('-'))
             {
                 model_path = Some(arg);
                 break;
@@ -69,41 +211,666 @@ impl Config
                         _ => return Err(ConfigError::ParseError(value))
                     }
                 },
+                "-f" | "--format" =>
+                {
+                    let value = next_value()?;
+                    match value.to_lowercase().as_str()
+                    {
+                        "image" =>
+                        {
+                            format = OutputFormat::Image;
+                        },
+                        "ppm" =>
+                        {
+                            format = OutputFormat::Ppm;
+                        },
+                        "exr" =>
+                        {
+                            format = OutputFormat::Exr;
+                        },
+                        _ => return Err(ConfigError::ParseError(value))
+                    }
+                },
+                "--winding" =>
+                {
+                    let value = next_value()?;
+                    match value.to_lowercase().as_str()
+                    {
+                        "ccw" =>
+                        {
+                            winding = Winding::CounterClockwise;
+                        },
+                        "cw" =>
+                        {
+                            winding = Winding::Clockwise;
+                        },
+                        "auto" =>
+                        {
+                            winding = Winding::Auto;
+                        },
+                        _ => return Err(ConfigError::ParseError(value))
+                    }
+                },
                 "-o" | "--output" =>
                 {
-                    filename = next_value()?.to_owned();
+                    filename = Some(next_value()?);
                 },
                 "-s" | "--size" =>
                 {
                     let value = next_value()?;
-                    let mut pair = value.split(' ');
+
+                    //both "800 600" and "800x600" work
+                    let spaced = value.trim().contains(' ');
+                    let mut pair = value.trim().split(|c: char|
+                    {
+                        if spaced
+                        {
+                            c == ' '
+                        } else
+                        {
+                            c == 'x' || c == 'X'
+                        }
+                    });
 
                     let mut parse_value = || -> Result<usize, ConfigError>
                     {
-                        pair.next().ok_or(ConfigError::DimensionMissing)?
+                        let dimension = pair.next().ok_or(ConfigError::DimensionMissing)?
                             .trim().parse::<usize>()
-                            .map_err(|_| ConfigError::ParseError(value.clone()))
+                            .map_err(|_| ConfigError::ParseError(value.clone()))?;
+
+                        if dimension == 0
+                        {
+                            return Err(ConfigError::ParseError(value.clone()));
+                        }
+
+                        Ok(dimension)
                     };
 
-                    size = Some((parse_value()?, parse_value()?));
+                    let dimensions = (parse_value()?, parse_value()?);
+
+                    if pair.next().is_some()
+                    {
+                        return Err(ConfigError::ParseError(value));
+                    }
+
+                    size = Some(dimensions);
                 },
                 "-d" | "--distance" =>
                 {
                     let value = next_value()?;
                     distance = value.trim().parse().map_err(|_| ConfigError::ParseError(value))?;
                 },
+                "--scene" =>
+                {
+                    scene_path = Some(next_value()?);
+                },
+                "--model" =>
+                {
+                    let path = next_value()?;
+
+                    let mut component = || -> Result<f64, ConfigError>
+                    {
+                        let value = next_value()?;
+                        value.trim().parse().map_err(|_| ConfigError::ParseError(value))
+                    };
+
+                    let position = Point3D{x: component()?, y: component()?, z: component()?};
+
+                    models.push((path, position));
+                },
+                "--fov" =>
+                {
+                    let value = next_value()?;
+                    fov = value.trim().parse().map_err(|_| ConfigError::ParseError(value.clone()))?;
+
+                    if fov <= 0.0 || fov >= 180.0
+                    {
+                        return Err(ConfigError::ParseError(value));
+                    }
+                },
+                "--roll" =>
+                {
+                    let value = next_value()?;
+                    roll = Some(value.trim().parse().map_err(|_| ConfigError::ParseError(value))?);
+                },
                 "-r" | "--rotation" =>
                 {
                     let value = next_value()?;
                     rotation = value.trim().parse().map_err(|_| ConfigError::ParseError(value))?;
                 },
+                "-a" | "--axis" =>
+                {
+                    let mut component = || -> Result<f64, ConfigError>
+                    {
+                        let value = next_value()?;
+                        value.trim().parse().map_err(|_| ConfigError::ParseError(value))
+                    };
+
+                    axis = (component()?, component()?, component()?);
+
+                    //normalizing a zero length axis would divide by zero
+                    if axis.0 == 0.0 && axis.1 == 0.0 && axis.2 == 0.0
+                    {
+                        return Err(ConfigError::ParseError(format!("{} {} {}", axis.0, axis.1, axis.2)));
+                    }
+                },
+                "--fit" => fit = true,
+                "--center" => center = true,
+                "--normalize" => normalize = true,
+                "--floor" =>
+                {
+                    let value = next_value()?;
+                    floor = Some(value.trim().parse().map_err(|_| ConfigError::ParseError(value))?);
+                },
+                "--weld" =>
+                {
+                    let value = next_value()?;
+                    let value: f64 = value.trim().parse().map_err(|_| ConfigError::ParseError(value))?;
+
+                    weld = Some(value.max(0.0));
+                },
+                "--translate" =>
+                {
+                    let mut component = || -> Result<f64, ConfigError>
+                    {
+                        let value = next_value()?;
+                        value.trim().parse().map_err(|_| ConfigError::ParseError(value))
+                    };
+
+                    translate = (component()?, component()?, component()?);
+                },
+                "--rotation-euler" =>
+                {
+                    let mut component = || -> Result<f64, ConfigError>
+                    {
+                        let value = next_value()?;
+                        value.trim().parse().map_err(|_| ConfigError::ParseError(value))
+                    };
+
+                    rotation_euler = Some((component()?, component()?, component()?));
+                },
+                "-l" | "--light" =>
+                {
+                    let mut component = || -> Result<f64, ConfigError>
+                    {
+                        let value = next_value()?;
+                        value.trim().parse().map_err(|_| ConfigError::ParseError(value))
+                    };
+
+                    let position = Point3D{x: component()?, y: component()?, z: component()?};
+
+                    lights.push(Light{position, intensity: component()?, kind: LightKind::Point});
+                },
+                "--spot" =>
+                {
+                    let mut component = || -> Result<f64, ConfigError>
+                    {
+                        let value = next_value()?;
+                        value.trim().parse().map_err(|_| ConfigError::ParseError(value))
+                    };
+
+                    let position = Point3D{x: component()?, y: component()?, z: component()?};
+                    let direction = Point3D{x: component()?, y: component()?, z: component()?};
+
+                    let inner_angle = component()?.to_radians();
+                    let outer_angle = component()?.to_radians();
+
+                    let kind = LightKind::Spot{direction, inner_angle, outer_angle};
+                    lights.push(Light{position, intensity: component()?, kind});
+                },
+                "--ambient" =>
+                {
+                    let value = next_value()?;
+                    ambient = value.trim().parse().map_err(|_| ConfigError::ParseError(value))?;
+                },
+                "--specular-strength" | "--specular-weight" =>
+                {
+                    let value = next_value()?;
+                    specular = value.trim().parse().map_err(|_| ConfigError::ParseError(value))?;
+                },
+                "--no-specular" => specular = 0.0,
+                "--diffuse-weight" =>
+                {
+                    let value = next_value()?;
+                    diffuse = value.trim().parse().map_err(|_| ConfigError::ParseError(value))?;
+                },
+                "--energy-conserving" => energy_conserving = true,
+                "--cel" =>
+                {
+                    let value = next_value()?;
+                    let bands = value.trim().parse::<usize>()
+                        .map_err(|_| ConfigError::ParseError(value.clone()))?;
+
+                    if bands == 0
+                    {
+                        return Err(ConfigError::ParseError(value));
+                    }
+
+                    cel_bands = Some(bands);
+                },
+                "--outline" =>
+                {
+                    let value = next_value()?;
+                    let thickness = value.trim().parse::<usize>()
+                        .map_err(|_| ConfigError::ParseError(value.clone()))?;
+
+                    let mut component = || -> Result<f64, ConfigError>
+                    {
+                        let value = next_value()?;
+                        value.trim().parse().map_err(|_| ConfigError::ParseError(value))
+                    };
+
+                    let color = Color::new(component()?, component()?, component()?);
+
+                    outline = Some(Outline{thickness, color, object: None});
+                },
+                "--outline-object" =>
+                {
+                    let value = next_value()?;
+                    outline_object = Some(value.trim().parse::<usize>()
+                        .map_err(|_| ConfigError::ParseError(value))?);
+                },
+                "--frames" =>
+                {
+                    let value = next_value()?;
+                    let amount = value.trim().parse::<usize>()
+                        .map_err(|_| ConfigError::ParseError(value.clone()))?;
+
+                    if amount == 0
+                    {
+                        return Err(ConfigError::ParseError(value));
+                    }
+
+                    frames = Some(amount);
+                },
+                "--sequence" =>
+                {
+                    sequence = Some(next_value()?);
+
+                    let value = next_value()?;
+                    let amount = value.trim().parse::<usize>()
+                        .map_err(|_| ConfigError::ParseError(value.clone()))?;
+
+                    if amount == 0
+                    {
+                        return Err(ConfigError::ParseError(value));
+                    }
+
+                    frames = Some(amount);
+                },
+                "--visualize" =>
+                {
+                    let value = next_value()?;
+                    match value.to_lowercase().as_str()
+                    {
+                        "normals" =>
+                        {
+                            visualization = Some(Visualization::Normals);
+                        },
+                        _ => return Err(ConfigError::ParseError(value))
+                    }
+                },
+                "--tonemap" =>
+                {
+                    let value = next_value()?;
+                    match value.to_lowercase().as_str()
+                    {
+                        "reinhard" =>
+                        {
+                            tonemap = Some(Tonemap::Reinhard);
+                        },
+                        _ => return Err(ConfigError::ParseError(value))
+                    }
+                },
+                "--fog" =>
+                {
+                    let mut component = || -> Result<f64, ConfigError>
+                    {
+                        let value = next_value()?;
+                        value.trim().parse().map_err(|_| ConfigError::ParseError(value))
+                    };
+
+                    let color = Color::new(component()?, component()?, component()?);
+
+                    fog = Some(Fog{color, start: component()?, end: component()?});
+                },
+                "--focus" =>
+                {
+                    let mut component = || -> Result<f64, ConfigError>
+                    {
+                        let value = next_value()?;
+                        value.trim().parse().map_err(|_| ConfigError::ParseError(value))
+                    };
+
+                    let (distance, aperture): (f64, f64) = (component()?, component()?);
+
+                    if !(distance.is_finite() && distance > 0.0 && aperture.is_finite() && aperture >= 0.0)
+                    {
+                        return Err(ConfigError::ParseError(format!("{distance} {aperture}")));
+                    }
+
+                    focus = Some((distance, aperture));
+                },
+                "--bloom" =>
+                {
+                    let mut component = || -> Result<f64, ConfigError>
+                    {
+                        let value = next_value()?;
+                        let component: f64 = value.trim().parse()
+                            .map_err(|_| ConfigError::ParseError(value.clone()))?;
+
+                        if !(component.is_finite() && component >= 0.0)
+                        {
+                            return Err(ConfigError::ParseError(value));
+                        }
+
+                        Ok(component)
+                    };
+
+                    bloom = Some(Bloom{threshold: component()?, intensity: component()?});
+                },
+                "--skybox" =>
+                {
+                    skybox = Some(next_value()?);
+                },
+                "--matcap" =>
+                {
+                    matcap = Some(next_value()?);
+                },
+                "--reflectivity" =>
+                {
+                    let value = next_value()?;
+                    let value: f64 = value.trim().parse().map_err(|_| ConfigError::ParseError(value))?;
+
+                    reflectivity = Some(value.clamp(0.0, 1.0));
+                },
                 "-u" | "--undeferred" => undeferred = true,
+                "--rasterizer" =>
+                {
+                    let value = next_value()?;
+                    match value.to_lowercase().as_str()
+                    {
+                        "scanline" =>
+                        {
+                            rasterizer = Rasterizer::Scanline;
+                        },
+                        "barycentric" =>
+                        {
+                            rasterizer = Rasterizer::Barycentric;
+                        },
+                        _ => return Err(ConfigError::ParseError(value))
+                    }
+                },
+                "--preview" => preview = true,
+                "--region" =>
+                {
+                    let mut component = || -> Result<usize, ConfigError>
+                    {
+                        let value = next_value()?;
+                        value.trim().parse().map_err(|_| ConfigError::ParseError(value))
+                    };
+
+                    let (x0, y0, x1, y1) = (component()?, component()?, component()?, component()?);
+
+                    if x0 >= x1 || y0 >= y1
+                    {
+                        return Err(ConfigError::ParseError(format!("{x0} {y0} {x1} {y1}")));
+                    }
+
+                    region = Some(Region{x0, y0, x1, y1});
+                },
+                "--crop" => crop = true,
+                "--supersample" =>
+                {
+                    let value = next_value()?;
+                    let factor = value.trim().parse::<usize>()
+                        .map_err(|_| ConfigError::ParseError(value.clone()))?;
+
+                    if factor == 0
+                    {
+                        return Err(ConfigError::ParseError(value));
+                    }
+
+                    supersample = Some(factor);
+                },
+                "--oit" => oit = true,
+                "--no-cull" => no_cull = true,
+                "--min-triangle-area" =>
+                {
+                    let value = next_value()?;
+                    min_triangle_area = value.trim().parse().map_err(|_| ConfigError::ParseError(value))?;
+                },
+                "-w" | "--wireframe" => wireframe = true,
+                "--smooth-lines" => smooth_lines = true,
+                "--gizmo" => gizmo = true,
+                "--bbox" => bounding_box = true,
+                "--double-sided" => double_sided = true,
+                "--zprepass" => zprepass = true,
+                "-t" | "--transparent" => transparent = true,
+                "-g" | "--gamma" =>
+                {
+                    let value = next_value()?;
+                    gamma = if value.to_lowercase() == "srgb"
+                    {
+                        Gamma::Srgb
+                    } else
+                    {
+                        let power: f64 = value.trim().parse()
+                            .map_err(|_| ConfigError::ParseError(value.clone()))?;
+
+                        if power <= 0.0
+                        {
+                            return Err(ConfigError::ParseError(value));
+                        }
+
+                        Gamma::Power(power)
+                    };
+                },
+                "--truecolor" => truecolor = true,
+                "--console-color" =>
+                {
+                    let value = next_value()?;
+                    match value.to_lowercase().as_str()
+                    {
+                        "cube" =>
+                        {
+                            console_color = Some(ColorMode::Palette);
+                        },
+                        "perceptual" =>
+                        {
+                            console_color = Some(ColorMode::Perceptual);
+                        },
+                        "truecolor" =>
+                        {
+                            console_color = Some(ColorMode::Truecolor);
+                        },
+                        _ => return Err(ConfigError::ParseError(value))
+                    }
+                },
+                "--charset" =>
+                {
+                    let value = next_value()?;
+                    if value.is_empty()
+                    {
+                        return Err(ConfigError::ParseError(value));
+                    }
+
+                    charset = value;
+                },
+                "--invert" => inverted = true,
+                "--dither" => dither = true,
+                "--char-aspect" =>
+                {
+                    let value = next_value()?;
+                    char_aspect = value.trim().parse().map_err(|_| ConfigError::ParseError(value.clone()))?;
+
+                    if char_aspect <= 0.0
+                    {
+                        return Err(ConfigError::ParseError(value));
+                    }
+                },
+                "--match-aspect" =>
+                {
+                    let value = next_value()?;
+
+                    //either a ratio like 16:9 or just the number
+                    let aspect = match value.split_once(':')
+                    {
+                        Some((width, height)) =>
+                        {
+                            let width: f64 = width.trim().parse().map_err(|_| ConfigError::ParseError(value.clone()))?;
+                            let height: f64 = height.trim().parse().map_err(|_| ConfigError::ParseError(value.clone()))?;
+
+                            width / height
+                        },
+                        None => value.trim().parse().map_err(|_| ConfigError::ParseError(value.clone()))?
+                    };
+
+                    if !(aspect.is_finite() && aspect > 0.0)
+                    {
+                        return Err(ConfigError::ParseError(value));
+                    }
+
+                    match_aspect = Some(aspect);
+                },
+                "--stats" => stats = true,
+                "--progress" => progress = true,
+                "--fps" =>
+                {
+                    let value = next_value()?;
+                    fps = value.trim().parse().map_err(|_| ConfigError::ParseError(value.clone()))?;
+
+                    if !(fps.is_finite() && fps > 0.0)
+                    {
+                        return Err(ConfigError::ParseError(value));
+                    }
+                },
+                "--rotation-speed" =>
+                {
+                    let value = next_value()?;
+                    rotation_speed = value.trim().parse().map_err(|_| ConfigError::ParseError(value.clone()))?;
+
+                    if !rotation_speed.is_finite()
+                    {
+                        return Err(ConfigError::ParseError(value));
+                    }
+                },
                 _ => return Err(ConfigError::InvalidArg(arg))
             }
         }
 
-        let model_path = model_path.ok_or(ConfigError::PathMissing)?;
-        Ok(Config{model_path, filename, draw_mode, size, distance, rotation, undeferred})
+        if model_path.is_none() && scene_path.is_none() && models.is_empty()
+        {
+            return Err(ConfigError::PathMissing);
+        }
+
+        //without an output name the file is named after whatever got rendered
+        let filename = filename.unwrap_or_else(||
+        {
+            let stem = model_path.as_ref().or(scene_path.as_ref())
+                .or(models.first().map(|(path, _)| path))
+                .filter(|path| *path != "-")
+                .and_then(|path| Path::new(path).file_stem())
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "output".to_owned());
+
+            let extension = match format
+            {
+                OutputFormat::Exr => "exr",
+                _ if frames.is_some() && sequence.is_none() => "gif",
+                _ => "png"
+            };
+
+            format!("{stem}.{extension}")
+        });
+
+        let exr_extension = filename.rsplit_once('.')
+            .map(|(_, extension)| extension.eq_ignore_ascii_case("exr"))
+            .unwrap_or(false);
+
+        if let (OutputFormat::Image, true) = (&format, exr_extension)
+        {
+            format = OutputFormat::Exr;
+        }
+
+        let outline = outline.map(|outline| Outline{object: outline_object, ..outline});
+
+        //scenes bring their own lights
+        if lights.is_empty() && scene_path.is_none()
+        {
+            lights.push(Light{
+                position: Point3D{x: 50.0, y: 20.0, z: 30.0},
+                intensity: 0.4,
+                kind: LightKind::Point
+            });
+        }
+        Ok(Config{
+            model_path,
+            scene_path,
+            models,
+            filename,
+            format,
+            draw_mode,
+            size,
+            distance,
+            fit,
+            center,
+            normalize,
+            weld,
+            floor,
+            translate,
+            fov,
+            roll,
+            rotation,
+            axis,
+            rotation_euler,
+            lights,
+            ambient,
+            specular,
+            diffuse,
+            energy_conserving,
+            cel_bands,
+            winding,
+            outline,
+            frames,
+            sequence,
+            visualization,
+            tonemap,
+            fog,
+            focus,
+            bloom,
+            skybox,
+            matcap,
+            reflectivity,
+            undeferred,
+            rasterizer,
+            preview,
+            supersample,
+            region,
+            crop,
+            oit,
+            no_cull,
+            min_triangle_area,
+            wireframe,
+            gizmo,
+            bounding_box,
+            smooth_lines,
+            double_sided,
+            zprepass,
+            transparent,
+            gamma,
+            truecolor,
+            console_color,
+            charset,
+            inverted,
+            dither,
+            char_aspect,
+            match_aspect,
+            fps,
+            rotation_speed,
+            stats,
+            progress
+        })
     }
 
     pub fn help_message(error: Option<ConfigError>) -> !
@@ -116,22 +883,95 @@ impl Config
                 ConfigError::InvalidArg(value) => format!("invalid argument: {value}"),
                 ConfigError::MissingValue(value) => format!("{value} argument is missing value"),
                 ConfigError::DimensionMissing => "missing height in size parameter".to_owned(),
-                ConfigError::PathMissing => "missing model path".to_owned()
+                ConfigError::PathMissing => "missing model or scene path".to_owned()
             };
 
             println!("{description}\n");
         }
 
         println!("usage: {} [args] path/to/model.obj", env::args().nth(0).unwrap());
+        println!("   or: {} [args] --scene path/to/scene.toml", env::args().nth(0).unwrap());
+        println!("   or: {} [args] - (reads an obj model from stdin)", env::args().nth(0).unwrap());
         println!("args:");
         println!("    -m, --mode          drawing mode (default picture)");
-        println!("    -s, --size          space separated size of the resulting image (default 512 by 512)");
+        println!("    --scene             renders the objects, lights and camera from a toml scene file");
+        println!("    --model             adds another model at x y z, the camera looks down -z, can be repeated");
+        println!("    -s, --size          size of the resulting image as WxH or \"W H\" (default 512 by 512)");
         println!("    -d, --distance      distance from the camera (default 50)");
+        println!("    --fit               picks a distance that keeps the whole model in frame");
+        println!("    --center            moves the middle of the model to its origin before rotating");
+        println!("    --normalize         scales the model so its longest side is 1 unit");
+        println!("    --floor             adds a ground plane at a height");
+        println!("    --weld              merges vertices closer than a distance and prints the vertex counts");
+        println!("    --translate         space separated x y z offset of the object on top of the distance");
+        println!("    --fov               vertical field of view in degrees (default 60)");
+        println!("    --roll              degrees the camera tilts to the right around where its looking");
         println!("    -r, --rotation      rotation of the object in radians (default 0.9)");
+        println!("    -a, --axis          space separated x y z axis the object rotates around (default 0.2 0.3 0.4)");
+        println!("    --rotation-euler    space separated pitch yaw roll in radians, replaces the rotation and axis");
+        println!("    -l, --light         adds a light at x y z with an intensity, can be repeated");
+        println!("    --spot              adds a spotlight at x y z pointing at dx dy dz with inner and outer");
+        println!("                        cone angles in degrees and an intensity, can be repeated");
+        println!("    --ambient           brightness of unlit surfaces (default 0.2)");
+        println!("    --specular-strength multiplier for the highlights (default 1), also --specular-weight");
+        println!("    --no-specular       turns the highlights off for purely diffuse shading");
+        println!("    --diffuse-weight    multiplier for the diffuse lighting (default 1)");
+        println!("    --energy-conserving the specular weight (0 to 1) gets taken away from the diffuse part");
+        println!("                        instead of adding on top, sharper highlights get brighter");
+        println!("    --cel               rounds the lighting to this many flat bands for a cartoon look");
+        println!("    --outline           thickness and r g b color of lines drawn over edges (deferred only)");
+        println!("    --outline-object    only outlines the object with this index, the main model is 0");
+        println!("    --frames            renders a full turn in this many frames as an animated gif");
+        println!("    --sequence          directory and frame count, saves a full turn as numbered pngs");
+        println!("    --visualize         shows a buffer instead of the lit image");
+        println!("    --tonemap           compresses bright colors instead of clipping them");
+        println!("    --fog               fades to an r g b color between start and end distances from the camera");
+        println!("    --focus             blurs by distance from the focal distance, aperture is the blur in pixels");
+        println!("    --bloom             adds a glow around pixels brighter than the threshold, scaled by intensity");
+        println!("    --skybox            fills the background from an equirectangular image");
+        println!("    --reflectivity      how much every surface mirrors the skybox (0 to 1)");
+        println!("    --matcap            shades by looking up the view space normal in an image, ignores lights");
         println!("    -u, --undeferred    disables deferred rendering, uses less ram but slower");
-        println!("    -o, --output        specify output filename for picture mode (default output.png)");
+        println!("    --rasterizer        how triangles get filled, scanline or barycentric (default barycentric)");
+        println!("    --preview           renders at half resolution, pictures get scaled back up");
+        println!("    --supersample       renders n times bigger and averages it down for antialiasing");
+        println!("    --region            x0 y0 x1 y1 pixels from the top left, only draws inside of them");
+        println!("    --crop              saves just the region instead of the whole frame");
+        println!("    --oit               blends see-through materials in any order (deferred only)");
+        println!("    --no-cull           draws back facing triangles too");
+        println!("    --min-triangle-area skips triangles with a smaller bounding box in pixels (default 0)");
+        println!("    --winding           which way front faces go around, ccw, cw or auto (default ccw)");
+        println!("    -w, --wireframe     draws only the edges of triangles");
+        println!("    --gizmo             shows the model axes (x red, y green, z blue) in a corner");
+        println!("    --bbox              outlines the bounding box of every model");
+        println!("    --smooth-lines      antialiases the wireframe lines (always undeferred)");
+        println!("    --double-sided      lights back faces as if they faced the camera");
+        println!("    --zprepass          with undeferred rendering fills depth first so only visible pixels get shaded");
+        println!("    --progress          prints how far along loading and drawing are");
+        println!("    -o, --output        specify output filename for picture mode (default named after the model)");
+        println!("                        {{n}} gets replaced with the frame number, saving every frame as its own png");
+        println!("    -f, --format        output format for picture mode (default image)");
+        println!("    -t, --transparent   leaves the background of the picture transparent");
+        println!("    -g, --gamma         gamma of picture mode output, srgb or a number (default srgb)");
+        println!("    --truecolor         uses 24 bit colors in console mode (detected from COLORTERM)");
+        println!("    --console-color     cube, perceptual or truecolor, perceptual picks the closest looking 256 color");
+        println!("    --charset           characters from darkest to brightest used in console mode");
+        println!("    --invert            reverses the console mode characters for dark on light");
+        println!("    --dither            ordered dithering for the cube console colors");
+        println!("    --char-aspect       width divided by height of a console character (default 0.5)");
+        println!("    --match-aspect      frames the same in every mode with this aspect (like 16:9), bars fill the rest");
+        println!("    --fps               frames per second in console mode (default 10)");
+        println!("    --rotation-speed    radians per second the object spins in console mode (default 2.5)");
+        println!("    --stats             prints triangle and pixel counts, with draw times in console mode");
         println!("modes:");
         println!("    picture, console");
+        println!("formats:");
+        println!("    image (saved to the output file), ppm (written to stdout)");
+        println!("    exr (float colors without gamma or clamping, picked for .exr output files)");
+        println!("visualizations:");
+        println!("    normals");
+        println!("tonemaps:");
+        println!("    reinhard");
 
         process::exit(1)
     }
@@ -0,0 +1,3 @@
+#![allow(clippy::suspicious_else_formatting, clippy::needless_range_loop, clippy::new_without_default, clippy::manual_is_multiple_of)]
+
+pub mod renderer;
@@ -1,93 +1,468 @@
 use std::{
     f64,
     env,
+    io::{self, Write},
     thread,
+    process,
+    path::{Path, PathBuf},
     time::{Duration, Instant}
 };
 
-use config::{DrawMode, Config};
+use config::{DrawMode, OutputFormat, Winding, Config};
 
-use renderer::{
+use rendererthingy::renderer::{
     Transform,
     Camera,
     Object,
-    common::{Point3D, Light},
+    FillMode,
+    RenderStats,
+    common::{Point3D, ShaderSettings, Texture, ColorSpace, DepthOfField, Region},
     model::Model,
+    scene::{Scene, SceneObject},
+    skybox_display::{Skybox, SkyboxDisplay},
+    bloom_display::BloomDisplay,
+    upscale_display::UpscaleDisplay,
+    downsample_display::DownsampleDisplay,
+    crop_display::CropDisplay,
+    letterbox_display::LetterboxDisplay,
     normal_drawable::{
         DrawableNormal,
         DrawableDisplay,
         DrawSurface,
+        DrawPass,
         NormalDrawable,
         DeferredDrawable
     },
     picture::Picture,
-    console_screen::ConsoleScreen
+    gif_writer::GifWriter,
+    sequence_writer::SequenceWriter,
+    ppm_writer::PpmWriter,
+    exr_writer::ExrWriter,
+    console_screen::{ColorMode, ConsoleScreen}
 };
 
 mod config;
 
-mod renderer;
 
+const FLOOR_EXTENT: f64 = 100.0;
+const FLOOR_TILES: usize = 40;
 
+//things that happen around drawing the objects
+struct Effects<'a>
+{
+    skybox: Option<&'a Skybox>,
+    depth_of_field: Option<DepthOfField>
+}
+
+#[allow(clippy::suspicious_else_formatting)]
 fn main()
 {
     let config = Config::parse(env::args().skip(1))
         .unwrap_or_else(|err| Config::help_message(Some(err)));
 
-    let size = mode_size(&config);
+    let aspect = mode_aspect(&config, frame_size(&config));
 
-    let model = Model::read_obj(&config.model_path).unwrap();
-    let transform = Transform::new(
-        (0.0, 0.0, -config.distance),
-        (1.0, 1.0, 1.0),
-        config.rotation,
-        (0.2, 0.3, 0.4)
-    );
+    let mut scene = if let Some(scene_path) = &config.scene_path
+    {
+        let mut scene = Scene::load(scene_path, aspect).unwrap_or_else(|err|
+        {
+            eprintln!("error loading scene {scene_path}: {err:?}");
+            process::exit(1)
+        });
 
-    let fov = 60.0;
-    let aspect = size.0 as f64 / size.1 as f64;
+        scene.lights.extend(config.lights.iter().cloned());
+
+        scene
+    } else
+    {
+        config_scene(&config, aspect)
+    };
+
+    //on top of whatever roll the scene file already has
+    if let Some(roll) = config.roll
+    {
+        scene.camera.roll(roll.to_radians());
+    }
+
+    //every one of these shares the camera and depth buffer with the rest of the scene
+    scene.objects.extend(config.models.iter().map(|(model_path, position)|
+    {
+        SceneObject{
+            model: load_model(&config, model_path),
+            transform: model_transform(&config, (position.x, position.y, position.z))
+        }
+    }));
+
+    for object in scene.objects.iter_mut()
+    {
+        let clockwise = match config.winding
+        {
+            Winding::CounterClockwise => false,
+            Winding::Clockwise => true,
+            Winding::Auto => object.model.clockwise()
+        };
+
+        if clockwise
+        {
+            object.model.flip_winding();
+        }
+    }
+
+    let environment = config.skybox.as_ref().map(|path|
+    {
+        Texture::load(Path::new(path), ColorSpace::Srgb).unwrap_or_else(|err|
+        {
+            eprintln!("error loading skybox {path}: {err}");
+            process::exit(1)
+        })
+    });
+
+    let matcap = config.matcap.as_ref().map(|path|
+    {
+        Texture::load(Path::new(path), ColorSpace::Srgb).unwrap_or_else(|err|
+        {
+            eprintln!("error loading matcap {path}: {err}");
+            process::exit(1)
+        })
+    });
+
+    let settings = ShaderSettings{
+        visualization: config.visualization,
+        ambient: config.ambient,
+        //bloom needs the colors before tonemapping and does it itself
+        tonemap: config.tonemap.filter(|_| config.bloom.is_none()),
+        fog: config.fog,
+        double_sided: config.double_sided,
+        environment: environment.clone(),
+        reflectivity: config.reflectivity,
+        specular: config.specular,
+        diffuse: config.diffuse,
+        energy_conserving: config.energy_conserving,
+        matcap,
+        cel_bands: config.cel_bands
+    };
+
+    let fill_mode = match (config.wireframe, config.smooth_lines)
+    {
+        (false, _) => FillMode::Solid,
+        (true, false) => FillMode::Wireframe,
+        (true, true) => FillMode::SmoothWireframe
+    };
+
+    let floor = config.floor.map(|height| Model::floor(height, FLOOR_EXTENT, FLOOR_TILES));
+
+    let mut objects = scene.objects(&settings);
 
+    if let Some(floor) = &floor
+    {
+        let transform = Transform::new((0.0, 0.0, 0.0), (1.0, 1.0, 1.0), 0.0, (0.0, 1.0, 0.0));
+
+        let mut object = Object::new(floor, transform, &scene.camera, &scene.lights, &settings);
+        object.set_spinning(false);
+
+        objects.push(object);
+    }
+
+    for (id, object) in objects.iter_mut().enumerate()
+    {
+        object.set_id(id);
+        object.set_culling(!config.no_cull);
+        object.set_min_area(config.min_triangle_area, mode_size(&config));
+        object.set_fill_mode(fill_mode);
+    }
+
+    let skybox = environment.map(|texture| Skybox::new(texture, &scene.camera));
+
+    let depth_of_field = config.focus.map(|(focus, aperture)|
+    {
+        DepthOfField{focus, aperture, near: scene.camera.near(), far: scene.camera.far()}
+    });
+
+    let effects = Effects{skybox: skybox.as_ref(), depth_of_field};
+
+    draw_full(&config, &mut objects, &effects);
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn load_model(config: &Config, model_path: &str) -> Model
+{
+    let model = if model_path == "-"
+    {
+        Model::read_obj_from_reader(io::stdin().lock())
+    } else if config.progress
+    {
+        let model = Model::read_with_progress(model_path, |fraction|
+        {
+            eprint!("\rloading {:.0}%", fraction * 100.0);
+        });
+
+        eprintln!();
+
+        model
+    } else
+    {
+        Model::read(model_path)
+    };
+
+    let mut model = model.unwrap_or_else(|err|
+    {
+        eprintln!("error loading model {model_path}: {err:?}");
+        process::exit(1)
+    });
+
+    //missing textures dont stop the render, the materials just use their colors
+    if !model.warnings.is_empty()
+    {
+        for warning in &model.warnings
+        {
+            eprintln!("{warning}");
+        }
+
+        eprintln!("{} textures failed to load in {model_path}", model.warnings.len());
+    }
+
+    if let Some(epsilon) = config.weld
+    {
+        let (before, after) = model.weld(epsilon);
+
+        eprintln!("welded {before} vertices into {after}");
+    }
+
+    if config.center
+    {
+        model.center();
+    }
+
+    if config.normalize
+    {
+        model.normalize_scale();
+    }
+
+    model
+}
+
+fn model_transform(config: &Config, position: (f64, f64, f64)) -> Transform
+{
+    let mut transform = Transform::new(position, (1.0, 1.0, 1.0), config.rotation, config.axis);
+
+    if let Some((pitch, yaw, roll)) = config.rotation_euler
+    {
+        transform.set_euler(pitch, yaw, roll);
+    }
+
+    transform
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn config_scene(config: &Config, aspect: f64) -> Scene
+{
+    //only --model objects, theyre added later like for a scene file
+    let objects = config.model_path.iter().map(|model_path|
+    {
+        let model = load_model(config, model_path);
+
+        let distance = if config.fit
+        {
+            fit_distance(&model, config.fov.to_radians(), aspect)
+        } else
+        {
+            config.distance
+        };
+
+        let (x, y, z) = config.translate;
+
+        SceneObject{model, transform: model_transform(config, (x, y, z - distance))}
+    }).collect();
+
+    let fov = config.fov;
     let camera = Camera::new(0.1, 100.0, (fov * f64::consts::PI) / 180.0, aspect);
 
-    let lights = [Light{
-        position: Point3D{x: 50.0, y: 20.0, z: 30.0},
-        //color: Color::new(1.0, 0.05, 0.08),
-        intensity: 0.4
-    }];
+    Scene{
+        camera,
+        lights: config.lights.clone(),
+        objects
+    }
+}
+
+//distance where a sphere around the model fits the narrower field of view at any rotation
+fn fit_distance(model: &Model, fov: f64, aspect: f64) -> f64
+{
+    let (low, high) = model.bounding_box();
+
+    let radius = [low.x, high.x].into_iter().flat_map(|x|
+    {
+        [low.y, high.y].into_iter().flat_map(move |y|
+        {
+            [low.z, high.z].into_iter().map(move |z| Point3D{x, y, z}.length())
+        })
+    }).fold(0.0, f64::max);
 
-    let mut object = Object::new(&model, transform, &camera, &lights);
+    let horizontal_fov = 2.0 * ((fov / 2.0).tan() * aspect).atan();
+    let half_fov = fov.min(horizontal_fov) / 2.0;
 
-    draw_full(config, &mut object);
+    radius / half_fov.sin()
 }
 
-fn draw<D: DrawableNormal>(object: &Object, drawable: &mut D)
+#[allow(clippy::suspicious_else_formatting)]
+fn draw<D: DrawableNormal>(config: &Config, objects: &[Object], drawable: &mut D) -> RenderStats
 {
     let mut surface = drawable.surface();
 
-    object.draw(&mut surface);
+    let passes: &[DrawPass] = if config.zprepass && surface.uses_passes()
+    {
+        &[DrawPass::DepthOnly, DrawPass::Shading]
+    } else
+    {
+        &[DrawPass::Full]
+    };
+
+    let mut stats = RenderStats::default();
+
+    for pass in passes
+    {
+        surface.set_pass(*pass);
+
+        //every pass sees the same triangles, only count them once
+        stats = RenderStats::default();
+
+        for (index, object) in objects.iter().enumerate()
+        {
+            stats += if config.progress
+            {
+                object.draw_with_progress(&mut surface, |fraction|
+                {
+                    let total = (index as f64 + fraction) / objects.len() as f64;
+
+                    eprint!("\rdrawing {:.0}%", total * 100.0);
+                })
+            } else
+            {
+                object.draw(&mut surface)
+            };
+        }
+
+        //the floor doesnt need a box around it
+        if config.bounding_box
+        {
+            for object in objects.iter().filter(|object| object.spinning())
+            {
+                object.draw_bounding_box(&mut surface);
+            }
+        }
+
+        //with a scene the gizmo follows its first object
+        if config.gizmo
+        {
+            if let Some(object) = objects.first()
+            {
+                object.draw_gizmo(&mut surface);
+            }
+        }
+    }
+
+    stats.pixels_shaded = surface.pixels_shaded();
+
+    if config.progress
+    {
+        eprintln!();
+    }
+
     surface.display();
+
+    stats
 }
 
-fn draw_length<D: DrawableNormal>(config: &Config, object: &mut Object, mut drawable: D)
+fn stats_message(stats: &RenderStats) -> String
+{
+    format!(
+        "{}/{} triangles drawn ({} backface culled, {} offscreen, {} too small), {} pixels shaded",
+        stats.triangles_drawn,
+        stats.triangles_total,
+        stats.backface_culled,
+        stats.frustum_culled,
+        stats.small_culled,
+        stats.pixels_shaded
+    )
+}
+
+fn rotate_objects(objects: &mut [Object], amount: f64)
+{
+    for object in objects.iter_mut().filter(|object| object.spinning())
+    {
+        let rotation = object.rotation();
+        object.set_rotation(rotation + amount);
+        object.update_transform();
+    }
+}
+
+fn draw_length<D: DrawableNormal>(config: &Config, objects: &mut [Object], mut drawable: D)
 {
     match config.draw_mode
     {
         DrawMode::Picture =>
         {
-            draw(&object, &mut drawable);
+            let frames = config.frames.unwrap_or(1);
+
+            let start_rotations = objects.iter().map(|object| object.rotation()).collect::<Vec<f64>>();
+
+            for frame in 0..frames
+            {
+                //from the start every frame so long sequences dont pile up rounding errors
+                let angle = frame as f64 / frames as f64 * f64::consts::TAU;
+
+                let spinning = objects.iter_mut().zip(start_rotations.iter())
+                    .filter(|(object, _)| object.spinning());
+
+                for (object, start_rotation) in spinning
+                {
+                    object.set_rotation(start_rotation + angle);
+                    object.update_transform();
+                }
+
+                let stats = draw(config, objects, &mut drawable);
+
+                if config.stats
+                {
+                    eprintln!("{}", stats_message(&stats));
+                }
+            }
         },
         DrawMode::Console =>
         {
-            let frame_delay = Duration::from_millis(100);
+            let frame_delay = Duration::from_secs_f64(1.0 / config.fps);
+
+            let mut last_frame = Instant::now();
             loop
             {
                 let frame_begin = Instant::now();
 
-                draw(&object, &mut drawable);
+                //spin by the time that actually passed so slow frames dont slow the spin
+                let elapsed = frame_begin.duration_since(last_frame).as_secs_f64();
+                last_frame = frame_begin;
+
+                rotate_objects(objects, config.rotation_speed * elapsed);
+
+                let stats = draw(config, objects, &mut drawable);
+
+                if config.stats
+                {
+                    let draw_time = frame_begin.elapsed().as_secs_f64();
+
+                    //the first frame comes right after last_frame got set
+                    let fps = if elapsed > 0.0 {1.0 / elapsed} else {0.0};
+
+                    //the image leaves the cursor on the line below it, no newline so nothing scrolls
+                    print!(
+                        "\x1b[0m\x1b[2Kdraw {:.1}ms, frame {:.1}ms ({:.1} fps), {}",
+                        draw_time * 1000.0,
+                        elapsed * 1000.0,
+                        fps,
+                        stats_message(&stats)
+                    );
 
-                let rotation = object.rotation();
-                object.set_rotation(rotation + 0.25);
-                object.update_transform();
+                    //a stats line that doesnt show up isnt worth stopping for
+                    let _ = io::stdout().flush();
+                }
 
                 if let Some(to_frame) = frame_delay.checked_sub(frame_begin.elapsed())
                 {
@@ -98,29 +473,262 @@ fn draw_length<D: DrawableNormal>(config: &Config, object: &mut Object, mut draw
     }
 }
 
-fn draw_mode<D: DrawableDisplay>(config: &Config, object: &mut Object, display: D)
+fn draw_mode<D: DrawableDisplay>(
+    config: &Config,
+    objects: &mut [Object],
+    effects: &Effects,
+    display: D
+)
+{
+    match config.region
+    {
+        Some(region) if config.crop => draw_scaled(config, objects, effects, CropDisplay::new(display, region)),
+        _ if config.match_aspect.is_some() =>
+        {
+            let display = LetterboxDisplay::new(display, output_size(config));
+
+            draw_scaled(config, objects, effects, display)
+        },
+        _ => draw_scaled(config, objects, effects, display)
+    }
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn draw_scaled<D: DrawableDisplay>(
+    config: &Config,
+    objects: &mut [Object],
+    effects: &Effects,
+    display: D
+)
+{
+    //the console just gets the smaller grid
+    if config.preview && matches!(config.draw_mode, DrawMode::Picture)
+    {
+        draw_bloom(config, objects, effects, UpscaleDisplay::new(display, frame_size(config)));
+    } else if let Some(factor) = supersample(config)
+    {
+        draw_bloom(config, objects, effects, DownsampleDisplay::new(display, factor));
+    } else
+    {
+        draw_bloom(config, objects, effects, display);
+    }
+}
+
+fn draw_bloom<D: DrawableDisplay>(
+    config: &Config,
+    objects: &mut [Object],
+    effects: &Effects,
+    display: D
+)
+{
+    match config.bloom
+    {
+        Some(bloom) =>
+        {
+            let mut display = BloomDisplay::new(display, bloom);
+            display.set_tonemap(config.tonemap);
+
+            draw_skybox(config, objects, effects, display)
+        },
+        None => draw_skybox(config, objects, effects, display)
+    }
+}
+
+fn draw_skybox<D: DrawableDisplay>(
+    config: &Config,
+    objects: &mut [Object],
+    effects: &Effects,
+    display: D
+)
+{
+    match effects.skybox
+    {
+        Some(skybox) =>
+        {
+            let mut display = SkyboxDisplay::new(display, skybox);
+
+            //with bloom the whole frame gets tonemapped after it including the sky
+            display.set_tonemap(config.tonemap.filter(|_| config.bloom.is_none()));
+
+            draw_drawable(config, objects, effects, display)
+        },
+        None => draw_drawable(config, objects, effects, display)
+    }
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn draw_drawable<D: DrawableDisplay>(config: &Config, objects: &mut [Object], effects: &Effects, display: D)
 {
     let size = mode_size(config);
 
-    if config.undeferred
+    //the deferred drawable keeps one surface per pixel so it cant blend smooth lines
+    if config.undeferred || (config.wireframe && config.smooth_lines)
     {
-        draw_length(config, object, &mut NormalDrawable::new(size, display));
+        let mut drawable = NormalDrawable::new(size, display);
+        drawable.set_region(mode_region(config));
+        drawable.set_rasterizer(config.rasterizer);
+        drawable.set_depth_of_field(effects.depth_of_field);
+
+        draw_length(config, objects, &mut drawable);
     } else
     {
-        draw_length(config, object, &mut DeferredDrawable::new(size, display));
+        let mut drawable = DeferredDrawable::new(size, display);
+        drawable.set_region(mode_region(config));
+        drawable.set_rasterizer(config.rasterizer);
+        drawable.set_oit(config.oit);
+        drawable.set_depth_of_field(effects.depth_of_field);
+        drawable.set_outline(config.outline);
+
+        draw_length(config, objects, &mut drawable);
     }
 }
 
-fn draw_full(config: Config, object: &mut Object)
+//numbered pngs instead of a gif when theres a sequence directory or a {n} in the filename
+fn sequence_pattern(config: &Config) -> Option<PathBuf>
 {
+    let numbered = config.filename.contains("{n}");
+
+    match &config.sequence
+    {
+        Some(directory) if numbered => Some(Path::new(directory).join(&config.filename)),
+        Some(directory) => Some(Path::new(directory).join("frame_{n}.png")),
+        None if numbered && config.frames.is_some() => Some(PathBuf::from(&config.filename)),
+        None => None
+    }
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+fn draw_full(config: &Config, objects: &mut [Object], effects: &Effects)
+{
+    match config.draw_mode
+    {
+        DrawMode::Picture => match config.format
+        {
+            OutputFormat::Image => if let Some(pattern) = sequence_pattern(config)
+            {
+                draw_mode(config, objects, effects, SequenceWriter::new(pattern, config.transparent, config.gamma))
+            } else if config.frames.is_some()
+            {
+                draw_mode(config, objects, effects, GifWriter::new(&config.filename, config.transparent, config.gamma))
+            } else
+            {
+                let picture = Picture::new(config.filename.clone(), config.transparent, config.gamma);
+
+                draw_mode(config, objects, effects, picture)
+            },
+            OutputFormat::Ppm => draw_mode(config, objects, effects, PpmWriter::new(config.gamma)),
+            OutputFormat::Exr =>
+            {
+                let writer = ExrWriter::new(config.filename.clone(), config.transparent);
+
+                draw_mode(config, objects, effects, writer)
+            }
+        },
+        DrawMode::Console =>
+        {
+            let color_mode = if let Some(color_mode) = config.console_color
+            {
+                color_mode
+            } else if config.truecolor || ConsoleScreen::supports_truecolor()
+            {
+                ColorMode::Truecolor
+            } else
+            {
+                ColorMode::Palette
+            };
+
+            let mut screen = ConsoleScreen::new(color_mode, &config.charset, config.inverted);
+            screen.set_dither(config.dither);
+
+            draw_mode(config, objects, effects, screen)
+        }
+    }
+}
+
+fn mode_aspect(config: &Config, size: (usize, usize)) -> f64
+{
+    //the frame only gets rounded to it, the camera can use it exactly
+    if let Some(aspect) = config.match_aspect
+    {
+        return aspect;
+    }
+
+    let aspect = size.0 as f64 / size.1 as f64;
+
     match config.draw_mode
     {
-        DrawMode::Picture => draw_mode(&config, object, Picture::new(config.filename.clone())),
-        DrawMode::Console => draw_mode(&config, object, ConsoleScreen::new())
+        DrawMode::Picture => aspect,
+        //characters are taller than they are wide
+        DrawMode::Console => aspect * config.char_aspect
     }
 }
 
+#[allow(clippy::suspicious_else_formatting)]
 fn mode_size(config: &Config) -> (usize, usize)
+{
+    let size = frame_size(config);
+
+    if config.preview
+    {
+        ((size.0 / 2).max(1), (size.1 / 2).max(1))
+    } else if let Some(factor) = supersample(config)
+    {
+        (size.0 * factor, size.1 * factor)
+    } else
+    {
+        size
+    }
+}
+
+//the region is picked in output pixels but the drawable might be rendering smaller or bigger
+fn mode_region(config: &Config) -> Option<Region>
+{
+    config.region.map(|region| region.scaled(frame_size(config), mode_size(config)))
+}
+
+//preview wins over supersampling, theres no point rendering bigger just to shrink it
+fn supersample(config: &Config) -> Option<usize>
+{
+    if config.preview || !matches!(config.draw_mode, DrawMode::Picture)
+    {
+        return None;
+    }
+
+    config.supersample.filter(|factor| *factor > 1)
+}
+
+//the biggest part of the output with the matched aspect, console characters are narrower than theyre tall
+#[allow(clippy::suspicious_else_formatting)]
+fn frame_size(config: &Config) -> (usize, usize)
+{
+    let size = output_size(config);
+
+    let aspect = match config.match_aspect
+    {
+        Some(aspect) => aspect,
+        None => return size
+    };
+
+    let pixel_aspect = match config.draw_mode
+    {
+        DrawMode::Picture => 1.0,
+        DrawMode::Console => config.char_aspect
+    };
+
+    let width = (size.1 as f64 * aspect / pixel_aspect).round() as usize;
+
+    if width <= size.0
+    {
+        (width.max(1), size.1)
+    } else
+    {
+        let height = (size.0 as f64 * pixel_aspect / aspect).round() as usize;
+
+        (size.0, height.clamp(1, size.1))
+    }
+}
+
+fn output_size(config: &Config) -> (usize, usize)
 {
     match config.draw_mode
     {
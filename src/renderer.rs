@@ -1,5 +1,5 @@
 use std::{
-    ops::{Deref, DerefMut}
+    ops::{Deref, DerefMut, AddAssign}
 };
 
 use common::{
@@ -9,11 +9,27 @@ use common::{
     Point3D,
     Mat3x3,
     Mat4x4,
+    Quaternion,
     Light,
-    FaceShader
+    FaceShader,
+    PixelId,
+    ShaderSettings,
+    ShaderValue,
+    DEFAULT_SHININESS,
+    INTERPOLATED_ZEROS
 };
 
-use normal_drawable::drawable::Drawable;
+use normal_drawable::{
+    DrawableNormal,
+    DrawSurface,
+    DrawableDisplay,
+    NormalDrawable,
+    DeferredDrawable,
+    DepthFunc,
+    drawable::Drawable
+};
+
+use buffer_display::BufferDisplay;
 
 use model::Model;
 
@@ -23,19 +39,99 @@ pub mod normal_drawable;
 
 pub mod model;
 
+pub mod scene;
+
+pub mod buffer_display;
+pub mod skybox_display;
+pub mod upscale_display;
+pub mod downsample_display;
+pub mod crop_display;
+pub mod letterbox_display;
+pub mod bloom_display;
 pub mod picture;
+pub mod ppm_writer;
+pub mod exr_writer;
+pub mod gif_writer;
+pub mod sequence_writer;
 pub mod console_screen;
 
 
+//draws the objects into one frame and hands back its colors, row by row from the top
+pub fn render_to_buffer(objects: &[Object], size: (usize, usize)) -> Vec<Color>
+{
+    let mut drawable = DeferredDrawable::new(size, BufferDisplay::new());
+
+    {
+        let mut drawable = &mut drawable;
+        let mut surface = drawable.surface();
+
+        for object in objects
+        {
+            object.draw(&mut surface);
+        }
+
+        surface.display();
+    }
+
+    drawable.into_display().into_colors()
+}
+
+//for drawing the same object over and over (like in benchmarks), the drawable keeps its buffers
+//between calls so only the first frame or a size change allocates them
+pub fn render_frame<T: DrawableDisplay>(object: &Object, mut drawable: &mut NormalDrawable<T>)
+{
+    let mut surface = drawable.surface();
+
+    object.draw(&mut surface);
+
+    surface.display();
+}
+
+//for geometry that never was a model, the points are already on screen: x and y go from 0 to 1
+//starting at the bottom left corner, the depth (ShaderValue::Depth) goes from -1 at the near plane
+//to 1 at the far one and pixels outside of that get skipped, the other interpolated values
+//are blended linearly across the screen and the shader gets them as they are
+//(the vertex color multiplies the shader color, so leaving it at 0 draws black)
+pub fn draw_triangles<'a>(drawable: &mut impl Drawable<'a>, triangles: &[[Point; 3]], shader: &'a FaceShader)
+{
+    for [p0, p1, p2] in triangles
+    {
+        drawable.triangle(*p0, *p1, *p2, shader);
+    }
+}
+
+//same as render_to_buffer but for screen space triangles, each one with its own shader
+pub fn render_triangles_to_buffer(triangles: &[([Point; 3], &FaceShader)], size: (usize, usize)) -> Vec<Color>
+{
+    let mut drawable = DeferredDrawable::new(size, BufferDisplay::new());
+
+    {
+        let mut drawable = &mut drawable;
+        let mut surface = drawable.surface();
+
+        for (points, shader) in triangles
+        {
+            draw_triangles(&mut surface, &[*points], shader);
+        }
+
+        surface.display();
+    }
+
+    drawable.into_display().into_colors()
+}
+
+#[derive(Clone)]
 pub struct Transform
 {
     position: (f64, f64, f64),
     scale: (f64, f64, f64),
     rotation: f64,
     rotation_axis: (f64, f64, f64),
+    orientation: Quaternion,
     combined: Mat4x4
 }
 
+#[allow(dead_code)]
 impl Transform
 {
     pub fn new(
@@ -45,9 +141,16 @@ impl Transform
         rotation_axis: (f64, f64, f64)
     ) -> Self
     {
-        let mut out = Transform{position, scale, rotation, rotation_axis, combined: Mat4x4::new()};
+        let mut out = Transform{
+            position,
+            scale,
+            rotation,
+            rotation_axis,
+            orientation: Quaternion::new(),
+            combined: Mat4x4::new()
+        };
 
-        out.combine();
+        out.set_rotation(rotation);
 
         out
     }
@@ -68,37 +171,60 @@ impl Transform
             [0.0, 0.0, 0.0, 1.0]
         ]};
 
-        let axis_magnitude = (self.rotation_axis.0 * self.rotation_axis.0
-            + self.rotation_axis.1 * self.rotation_axis.1
-            + self.rotation_axis.2 * self.rotation_axis.2).sqrt();
-
-        //normalize the axis
-        let axis = (self.rotation_axis.0 / axis_magnitude,
-            self.rotation_axis.1 / axis_magnitude,
-            self.rotation_axis.2 / axis_magnitude);
+        let rotate_mat = self.orientation.to_mat4x4();
 
-        let ca = self.rotation.cos();
-        let nca = 1.0 - ca;
+        self.combined = translate_mat * rotate_mat * scale_mat;
+    }
 
-        let sa = self.rotation.sin();
+    pub fn set_position(&mut self, position: (f64, f64, f64))
+    {
+        self.position = position;
 
-        let rotate_mat = Mat4x4{mat: [
-            [ca+axis.0*axis.0*nca, axis.0*axis.1*nca-axis.2*sa, axis.0*axis.2*nca+axis.1*sa, 0.0],
-            [axis.1*axis.0*nca+axis.2*sa, ca+axis.1*axis.1*nca, axis.1*axis.2*nca-axis.0*sa, 0.0],
-            [axis.2*axis.0*nca-axis.1*sa, axis.2*axis.1*nca+axis.0*sa, ca+axis.2*axis.2*nca, 0.0],
-            [0.0, 0.0, 0.0, 1.0]
-        ]};
+        self.combine();
+    }
 
-        self.combined = translate_mat * rotate_mat * scale_mat;
+    pub fn position(&self) -> (f64, f64, f64)
+    {
+        self.position
     }
 
     pub fn set_rotation(&mut self, rotation: f64)
     {
         self.rotation = rotation;
 
+        let (x, y, z) = self.rotation_axis;
+        self.orientation = Quaternion::from_axis_angle(Point3D{x, y, z}, rotation);
+
+        self.combine();
+    }
+
+    //the axis and angle follow the orientation so set_rotation keeps spinning from here
+    pub fn set_orientation(&mut self, orientation: Quaternion)
+    {
+        let (axis, rotation) = orientation.to_axis_angle();
+
+        self.orientation = orientation;
+        self.rotation = rotation;
+        self.rotation_axis = (axis.x, axis.y, axis.z);
+
         self.combine();
     }
 
+    //yaw around y, then pitch around x, then roll around z
+    pub fn set_euler(&mut self, pitch: f64, yaw: f64, roll: f64)
+    {
+        let pitch = Quaternion::from_axis_angle(Point3D{x: 1.0, y: 0.0, z: 0.0}, pitch);
+        let yaw = Quaternion::from_axis_angle(Point3D{x: 0.0, y: 1.0, z: 0.0}, yaw);
+        let roll = Quaternion::from_axis_angle(Point3D{x: 0.0, y: 0.0, z: 1.0}, roll);
+
+        self.set_orientation(yaw * pitch * roll);
+    }
+
+    pub fn orientation(&self) -> Quaternion
+    {
+        self.orientation
+    }
+
     pub fn rotation(&self) -> f64
     {
         self.rotation
@@ -116,20 +242,87 @@ pub struct Camera
     far: f64,
     fov: f64,
     aspect: f64,
-    mat: Mat4x4
+    mat: Mat4x4,
+    view: Mat4x4,
+    position: Point3D
 }
 
+#[allow(dead_code)]
 impl Camera
 {
     pub fn new(near: f64, far: f64, fov: f64, aspect: f64) -> Self
     {
-        let mut out = Camera{near, far, fov, aspect, mat: Mat4x4::new()};
+        let mut out = Camera{
+            near,
+            far,
+            fov,
+            aspect,
+            mat: Mat4x4::new(),
+            view: Mat4x4::new(),
+            position: Point3D{x: 0.0, y: 0.0, z: 0.0}
+        };
 
         out.calculate_matrix();
 
         out
     }
 
+    //the view matrix takes world space into camera space, where the camera looks down negative z
+    pub fn set_view(&mut self, view: Mat4x4)
+    {
+        let position = view.inverse() * [0.0, 0.0, 0.0, 1.0];
+
+        self.view = view;
+        self.position = Point3D{x: position[0], y: position[1], z: position[2]};
+    }
+
+    //positive roll tilts the camera to the right, turning the image counterclockwise
+    pub fn look_at(&mut self, eye: Point3D, target: Point3D, up: Point3D, roll: f64)
+    {
+        let forward = (target - eye).normalized();
+
+        let up = Quaternion::from_axis_angle(forward, roll).to_mat4x4() * [up.x, up.y, up.z, 0.0];
+        let up = Point3D{x: up[0], y: up[1], z: up[2]};
+
+        let side = forward.cross(up).normalized();
+        let up = side.cross(forward);
+
+        self.set_view(Mat4x4{mat: [
+            [side.x, side.y, side.z, -side.dot(eye)],
+            [up.x, up.y, up.z, -up.dot(eye)],
+            [-forward.x, -forward.y, -forward.z, forward.dot(eye)],
+            [0.0, 0.0, 0.0, 1.0]
+        ]});
+    }
+
+    //rolls around the current view direction, same direction as in look_at
+    pub fn roll(&mut self, angle: f64)
+    {
+        let rotation = Quaternion::from_axis_angle(Point3D{x: 0.0, y: 0.0, z: 1.0}, angle).to_mat4x4();
+
+        self.set_view(rotation * self.view);
+    }
+
+    pub fn view_matrix(&self) -> Mat4x4
+    {
+        self.view
+    }
+
+    pub fn position(&self) -> Point3D
+    {
+        self.position
+    }
+
+    pub fn near(&self) -> f64
+    {
+        self.near
+    }
+
+    pub fn far(&self) -> f64
+    {
+        self.far
+    }
+
     fn calculate_matrix(&mut self)
     {
         let th_fov = (self.fov / 2.0).tan();
@@ -151,16 +344,72 @@ impl Camera
     }
 }
 
+const GIZMO_LENGTH: f64 = 0.08;
+const GIZMO_COLORS: [Color; 3] = [
+    Color{r: 1.0, g: 0.0, b: 0.0},
+    Color{r: 0.0, g: 1.0, b: 0.0},
+    Color{r: 0.0, g: 0.0, b: 1.0}
+];
+
+const BOUNDING_BOX_COLOR: Color = Color{r: 1.0, g: 0.0, b: 1.0};
+
+const LINE_COLOR: Color = Color{r: 1.0, g: 1.0, b: 1.0};
+
+//triangle counts come from the objects, shaded pixels from the surface they drew on
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderStats
+{
+    pub triangles_total: usize,
+    pub backface_culled: usize,
+    pub frustum_culled: usize,
+    pub small_culled: usize,
+    pub triangles_drawn: usize,
+    pub pixels_shaded: usize
+}
+
+impl AddAssign for RenderStats
+{
+    fn add_assign(&mut self, other: Self)
+    {
+        self.triangles_total += other.triangles_total;
+        self.backface_culled += other.backface_culled;
+        self.frustum_culled += other.frustum_culled;
+        self.small_culled += other.small_culled;
+        self.triangles_drawn += other.triangles_drawn;
+        self.pixels_shaded += other.pixels_shaded;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillMode
+{
+    Solid,
+    Wireframe,
+    //antialiased lines, only the undeferred drawable can blend them
+    SmoothWireframe
+}
+
 pub struct Object<'a>
 {
     model: &'a Model,
     transform: Transform,
     camera: &'a Camera,
     lights: &'a [Light],
+    settings: &'a ShaderSettings,
+    id: usize,
+    culling: bool,
+    //as a fraction of the whole screen so it doesnt need the size when drawing
+    min_area: f64,
+    spinning: bool,
+    fill_mode: FillMode,
     points: Vec<Point3D>,
     world_points: Vec<Point3D>,
+    view_points: Vec<Point3D>,
     normals: Vec<Point3D>,
-    face_shaders: Vec<FaceShader<'a>>
+    face_shaders: Vec<FaceShader<'a>>,
+    gizmo_shaders: Vec<FaceShader<'a>>,
+    bounding_box_shader: Option<FaceShader<'a>>,
+    line_shader: Option<FaceShader<'a>>
 }
 
 impl<'a> Object<'a>
@@ -169,7 +418,8 @@ impl<'a> Object<'a>
         model: &'a Model,
         transform: Transform,
         camera: &'a Camera,
-        lights: &'a [Light]
+        lights: &'a [Light],
+        settings: &'a ShaderSettings
     ) -> Self
     {
         let mut out = Object{
@@ -177,10 +427,20 @@ impl<'a> Object<'a>
             transform,
             camera,
             lights,
+            settings,
+            id: 0,
+            culling: true,
+            min_area: 0.0,
+            spinning: true,
+            fill_mode: FillMode::Solid,
             points: Vec::new(),
             world_points: Vec::new(),
+            view_points: Vec::new(),
             normals: Vec::new(),
-            face_shaders: Vec::new()
+            face_shaders: Vec::new(),
+            gizmo_shaders: Vec::new(),
+            bounding_box_shader: None,
+            line_shader: None
         };
 
         out.update_transform();
@@ -188,50 +448,191 @@ impl<'a> Object<'a>
         out
     }
 
-    fn backface(p0: Point3D, p1: Point3D, p2: Point3D) -> (bool, Point3D)
+    //without culling back faces get drawn too, open meshes show their insides
+    pub fn set_culling(&mut self, culling: bool)
+    {
+        self.culling = culling;
+    }
+
+    //triangles with a smaller bounding box (in pixels of a frame this big) get skipped
+    pub fn set_min_area(&mut self, area: f64, size: (usize, usize))
+    {
+        self.min_area = area / (size.0 * size.1).max(1) as f64;
+    }
+
+    //things like the floor stay put while everything else spins
+    pub fn set_spinning(&mut self, spinning: bool)
+    {
+        self.spinning = spinning;
+    }
+
+    pub fn spinning(&self) -> bool
+    {
+        self.spinning
+    }
+
+    //ends up in the deferred id buffer for every pixel this object covers
+    pub fn set_id(&mut self, id: usize)
+    {
+        self.id = id;
+
+        let shaders = self.face_shaders.iter_mut()
+            .chain(self.gizmo_shaders.iter_mut())
+            .chain(self.bounding_box_shader.iter_mut())
+            .chain(self.line_shader.iter_mut());
+
+        for shader in shaders
+        {
+            shader.id.object = id;
+        }
+    }
+
+    pub fn set_fill_mode(&mut self, fill_mode: FillMode)
+    {
+        self.fill_mode = fill_mode;
+    }
+
+    fn backface(p0: Point3D, p1: Point3D, p2: Point3D) -> bool
     {
         let normal = (p1 - p0).cross(p2 - p0);
 
-        (p0.dot(normal) >= 0.0, normal)
+        p0.dot(normal) >= 0.0
     }
 
-    pub fn draw<'d>(&'d self, drawable: &mut impl Drawable<'d>)
+    //true if the whole triangle lies on the outside of one of the screen edges
+    fn offscreen(points: [Point3D; 3], view_points: [Point3D; 3]) -> bool
+    {
+        //points behind the camera get flipped by the projection so their bounds mean nothing
+        if view_points.iter().any(|point| point.z >= 0.0)
+        {
+            return false;
+        }
+
+        let all = |check: fn(&Point3D) -> bool| points.iter().all(check);
+
+        all(|point| point.x < 0.0) || all(|point| point.x >= 1.0)
+            || all(|point| point.y < 0.0) || all(|point| point.y >= 1.0)
+            || all(|point| point.z > 1.0)
+    }
+
+    pub fn draw<'d>(&'d self, drawable: &mut impl Drawable<'d>) -> RenderStats
     where 'a: 'd
     {
-        for t in 0..(self.model.indices.len()/3)
+        self.draw_with_progress(drawable, |_| {})
+    }
+
+    //progress gets called with the fraction of triangles drawn so far
+    pub fn draw_with_progress<'d>(
+        &'d self,
+        drawable: &mut impl Drawable<'d>,
+        mut progress: impl FnMut(f64)
+    ) -> RenderStats
+    where 'a: 'd
+    {
+        let triangles = self.model.indices.len() / 3;
+        let mut last_percent = 0;
+
+        let mut stats = RenderStats{triangles_total: triangles, ..Default::default()};
+
+        //models without any vertices have nothing to draw, not even lines
+        if self.model.vertices.is_empty()
         {
-            self.draw_triangle(drawable, t);
+            return stats;
         }
+
+        for t in 0..triangles
+        {
+            self.draw_triangle(drawable, t, &mut stats);
+
+            let percent = (t + 1) * 100 / triangles;
+            if percent != last_percent
+            {
+                last_percent = percent;
+                progress((t + 1) as f64 / triangles as f64);
+            }
+        }
+
+        self.draw_lines(drawable);
+
+        stats
     }
 
-    fn draw_triangle<'d>(&'d self, drawable: &mut impl Drawable<'d>, start_index: usize)
+    fn draw_triangle<'d>(
+        &'d self,
+        drawable: &mut impl Drawable<'d>,
+        start_index: usize,
+        stats: &mut RenderStats
+    )
     where 'a: 'd
     {
         let meta_index = |point_index| start_index * 3 + point_index;
         let index_at = |point_index| self.model.indices[meta_index(point_index)];
         let world_point = |point_index| self.world_points[index_at(point_index)];
+        let view_point = |point_index| self.view_points[index_at(point_index)];
 
         let world_points = [world_point(0), world_point(1), world_point(2)];
+        let view_points = [view_point(0), view_point(1), view_point(2)];
 
-        let (is_backface, normal) =
-            Self::backface(world_points[0], world_points[1], world_points[2]);
+        //the camera sits at the origin in view space
+        let is_backface = Self::backface(view_points[0], view_points[1], view_points[2]);
 
-        if is_backface
+        if is_backface && self.culling
         {
+            stats.backface_culled += 1;
+
             return;
         }
 
+        let points = [
+            self.points[index_at(0)],
+            self.points[index_at(1)],
+            self.points[index_at(2)]
+        ];
+
+        let in_front = |point: &Point3D| point.z < -self.camera.near();
+
+        if Self::offscreen(points, view_points) || !view_points.iter().any(in_front)
+        {
+            stats.frustum_culled += 1;
+
+            return;
+        }
+
+        //clipped triangles reach past the camera so theyre never tiny
+        if self.min_area > 0.0 && view_points.iter().all(in_front)
+        {
+            let span = |value: fn(&Point3D) -> f64|
+            {
+                let values = points.iter().map(value);
+
+                values.clone().fold(f64::MIN, f64::max) - values.fold(f64::MAX, f64::min)
+            };
+
+            if span(|point| point.x) * span(|point| point.y) < self.min_area
+            {
+                stats.small_culled += 1;
+
+                return;
+            }
+        }
+
+        stats.triangles_drawn += 1;
+
+        //models without normals get shaded flat, the lights are in world space so the normal is too
+        let face_normal = self.normals.is_empty().then(||
+        {
+            (world_points[1] - world_points[0]).cross(world_points[2] - world_points[0]).normalized()
+        });
+
         let point_at = |point_index|
         {
             let meta_index = meta_index(point_index);
             let index = index_at(point_index);
 
-            let normal: Point3D = if !self.normals.is_empty()
-            {
-                self.normals[meta_index]
-            } else
+            let normal: Point3D = match face_normal
             {
-                normal.normalized()
+                Some(normal) => normal,
+                None => self.normals[meta_index]
             };
 
             let uv: Point2D = if !self.model.uvs.is_empty()
@@ -242,6 +643,9 @@ impl<'a> Object<'a>
                 Point2D{x: 0.0, y: 0.0}
             };
 
+            let vertex_color: Color = self.model.vertex_colors.get(index).copied().flatten()
+                .unwrap_or(Color::new(1.0, 1.0, 1.0));
+
             let point: Point3D = self.points[index];
             let world_point: Point3D = world_points[point_index];
 
@@ -249,7 +653,8 @@ impl<'a> Object<'a>
                 point.z,
                 world_point.x, world_point.y, world_point.z,
                 normal.x, normal.y, normal.z,
-                uv.x, uv.y
+                uv.x, uv.y,
+                vertex_color.r, vertex_color.g, vertex_color.b
             ];
 
             Point{
@@ -261,15 +666,97 @@ impl<'a> Object<'a>
 
         let shader = &self.face_shaders[start_index];
 
-        drawable.triangle(point_at(0), point_at(1), point_at(2), shader);
+        let mut draw_points = |p0, p1, p2|
+        {
+            match self.fill_mode
+            {
+                FillMode::Solid => drawable.triangle(p0, p1, p2, shader),
+                FillMode::Wireframe => drawable.triangle_wireframe(p0, p1, p2, shader),
+                FillMode::SmoothWireframe => drawable.triangle_wireframe_smooth(p0, p1, p2, shader)
+            }
+        };
+
+        let corners = [point_at(0), point_at(1), point_at(2)];
+
+        if view_points.iter().all(in_front)
+        {
+            draw_points(corners[0], corners[1], corners[2]);
+        } else
+        {
+            //points behind the camera would get flipped by the projection, cut them off first
+            let clipped = self.clip_near(view_points, corners);
+
+            for i in 1..(clipped.len() - 1)
+            {
+                draw_points(clipped[0], clipped[i], clipped[i + 1]);
+            }
+        }
+    }
+
+    //view space to 0 to 1 screen coordinates with the ndc depth in z
+    fn project(&self, view_point: Point3D) -> Point3D
+    {
+        let transformed = self.camera.matrix() * [view_point.x, view_point.y, view_point.z, 1.0];
+
+        Point3D{
+            x: (transformed[0] / transformed[3] + 1.0) / 2.0,
+            y: (transformed[1] / transformed[3] + 1.0) / 2.0,
+            z: transformed[2] / transformed[3]
+        }
+    }
+
+    //sutherland hodgman against the near plane, gives back 3 or 4 points
+    fn clip_near(&self, view_points: [Point3D; 3], corners: [Point; 3]) -> Vec<Point>
+    {
+        let near = -self.camera.near();
+
+        let mut clipped = Vec::with_capacity(4);
+
+        for i in 0..3
+        {
+            let j = (i + 1) % 3;
+
+            let (a, b) = (view_points[i], view_points[j]);
+
+            let a_inside = a.z < near;
+            if a_inside
+            {
+                clipped.push(corners[i]);
+            }
+
+            if a_inside != (b.z < near)
+            {
+                let amount = (near - a.z) / (b.z - a.z);
+
+                let view_point = a.lerp(b, amount);
+
+                let mut interpolated = INTERPOLATED_ZEROS;
+                for (index, value) in interpolated.iter_mut().enumerate()
+                {
+                    *value = corners[i].interpolated[index] * (1.0 - amount)
+                        + corners[j].interpolated[index] * amount;
+                }
+
+                let point = self.project(view_point);
+
+                interpolated[ShaderValue::Depth as usize] = point.z;
+
+                clipped.push(Point{x: point.x, y: point.y, interpolated});
+            }
+        }
+
+        clipped
     }
 
     pub fn update_transform(&mut self)
     {
         let transform_matrix = self.transform.matrix();
+        let view_matrix = self.camera.view_matrix();
         let projection_matrix = self.camera.matrix();
 
-        (self.points, self.world_points) = (0..(self.model.vertices.len()/3)).map(|index|
+        let to_point = |point: [f64; 4]| Point3D{x: point[0], y: point[1], z: point[2]};
+
+        let points = (0..(self.model.vertices.len()/3)).map(|index|
         {
             let point = [
                 self.model.vertices[index * 3],
@@ -279,7 +766,8 @@ impl<'a> Object<'a>
             ];
 
             let world_point = transform_matrix * point;
-            let transformed = projection_matrix * world_point;
+            let view_point = view_matrix * world_point;
+            let transformed = projection_matrix * view_point;
 
             let point = Point3D{
                 x: (transformed[0] / transformed[3] + 1.0) / 2.0,
@@ -287,11 +775,15 @@ impl<'a> Object<'a>
                 z: transformed[2] / transformed[3]
             };
 
-            (point, Point3D{x: world_point[0], y: world_point[1], z: world_point[2]})
-        }).unzip();
+            (point, to_point(world_point), to_point(view_point))
+        }).collect::<Vec<_>>();
+
+        self.points = points.iter().map(|(point, _, _)| *point).collect();
+        self.world_points = points.iter().map(|(_, world_point, _)| *world_point).collect();
+        self.view_points = points.iter().map(|(_, _, view_point)| *view_point).collect();
 
 
-        let normal_matrix = Mat3x3::from(transform_matrix).transpose().inverse();
+        let normal_matrix = Mat3x3::from(transform_matrix.inverse().transpose());
         self.normals = self.model.normals.iter().map(|normal|
         {
             let normal = normal_matrix * [normal.x, normal.y, normal.z];
@@ -301,20 +793,226 @@ impl<'a> Object<'a>
         self.face_shaders = self.model.material_indices.iter().map(|material_index|
         {
             let lights = self.lights;
+            let settings = self.settings;
+            let camera_position = self.camera.position();
+            let camera_view = self.camera.view_matrix();
+            let id = self.id;
 
             if let Some(index) = material_index
             {
                 let material = &self.model.materials[*index];
 
                 let color = material.diffuse_color.unwrap_or(Color::new(0.5, 0.5, 0.5));
-                let texture = material.diffuse_texture.as_ref();
-
-                FaceShader{color, lights, texture}
+                let texture = material.diffuse_texture.as_deref();
+
+                let double_sided = material.double_sided || settings.double_sided;
+
+                FaceShader{
+                    color,
+                    lights,
+                    camera_position,
+                    camera_view,
+                    texture,
+                    emissive: material.emissive,
+                    emissive_texture: material.emissive_texture.as_deref(),
+                    shininess: material.shininess.unwrap_or(DEFAULT_SHININESS),
+                    shininess_texture: material.shininess_texture.as_deref(),
+                    double_sided,
+                    reflectivity: settings.reflectivity.unwrap_or(material.reflectivity),
+                    opacity: material.opacity,
+                    unlit: false,
+                    id: PixelId{object: id, material: Some(*index)},
+                    settings
+                }
             } else
             {
-                FaceShader{color: Color::new(0.5, 0.5, 0.5), lights, texture: None}
+                FaceShader{
+                    color: Color::new(0.5, 0.5, 0.5),
+                    lights,
+                    camera_position,
+                    camera_view,
+                    texture: None,
+                    emissive: None,
+                    emissive_texture: None,
+                    shininess: DEFAULT_SHININESS,
+                    shininess_texture: None,
+                    double_sided: settings.double_sided,
+                    reflectivity: settings.reflectivity.unwrap_or(0.0),
+                    opacity: 1.0,
+                    unlit: false,
+                    id: PixelId{object: id, material: None},
+                    settings
+                }
             }
         }).collect();
+
+        self.gizmo_shaders = GIZMO_COLORS.iter().map(|color| self.unlit_shader(*color)).collect();
+        self.bounding_box_shader = Some(self.unlit_shader(BOUNDING_BOX_COLOR));
+        self.line_shader = Some(self.unlit_shader(LINE_COLOR));
+    }
+
+    fn unlit_shader(&self, color: Color) -> FaceShader<'a>
+    {
+        FaceShader{
+            camera_position: self.camera.position(),
+            camera_view: self.camera.view_matrix(),
+            double_sided: true,
+            unlit: true,
+            id: PixelId{object: self.id, material: None},
+            ..FaceShader::new(color, self.lights, self.settings)
+        }
+    }
+
+    //the edges of the models bounding box, moving and rotating along with it
+    pub fn draw_bounding_box<'d>(&'d self, drawable: &mut impl Drawable<'d>)
+    where 'a: 'd
+    {
+        let shader = if let Some(shader) = &self.bounding_box_shader
+        {
+            shader
+        } else
+        {
+            return;
+        };
+
+        let (low, high) = self.model.bounding_box();
+
+        let matrix = self.camera.view_matrix() * self.transform.matrix();
+
+        let corners = (0..8).map(|corner|
+        {
+            let pick = |bit: usize, low: f64, high: f64| if (corner & bit) != 0 {high} else {low};
+
+            let point = [pick(1, low.x, high.x), pick(2, low.y, high.y), pick(4, low.z, high.z), 1.0];
+            let point = matrix * point;
+
+            Point3D{x: point[0], y: point[1], z: point[2]}
+        }).collect::<Vec<Point3D>>();
+
+        //every pair of corners that differ in exactly one axis
+        for a in 0..8_usize
+        {
+            for bit in [1, 2, 4]
+            {
+                let b = a | bit;
+                if b == a
+                {
+                    continue;
+                }
+
+                self.draw_view_line(drawable, corners[a], corners[b], shader);
+            }
+        }
+    }
+
+    //a line between 2 view space points, with the part behind the camera cut off
+    fn draw_view_line<'d>(
+        &'d self,
+        drawable: &mut impl Drawable<'d>,
+        mut start: Point3D,
+        mut end: Point3D,
+        shader: &'d FaceShader
+    )
+    where 'a: 'd
+    {
+        let near = -self.camera.near();
+
+        if start.z >= near && end.z >= near
+        {
+            return;
+        }
+
+        if start.z >= near
+        {
+            start = end.lerp(start, (near - end.z) / (start.z - end.z));
+        } else if end.z >= near
+        {
+            end = start.lerp(end, (near - start.z) / (end.z - start.z));
+        }
+
+        let point = |view_point: Point3D|
+        {
+            let point = self.project(view_point);
+
+            let mut interpolated = INTERPOLATED_ZEROS;
+
+            interpolated[ShaderValue::Depth as usize] = point.z;
+            interpolated[ShaderValue::ColorR as usize] = 1.0;
+            interpolated[ShaderValue::ColorG as usize] = 1.0;
+            interpolated[ShaderValue::ColorB as usize] = 1.0;
+
+            Point{x: point.x, y: point.y, interpolated}
+        };
+
+        drawable.line(point(start), point(end), shader);
+    }
+
+    //polylines from the model, they have no faces so they skip all the lighting
+    fn draw_lines<'d>(&'d self, drawable: &mut impl Drawable<'d>)
+    where 'a: 'd
+    {
+        let shader = if let Some(shader) = &self.line_shader
+        {
+            shader
+        } else
+        {
+            return;
+        };
+
+        for segment in self.model.line_indices.chunks_exact(2)
+        {
+            self.draw_view_line(drawable, self.view_points[segment[0]], self.view_points[segment[1]], shader);
+        }
+    }
+
+    //x y and z axes of the object in the bottom left corner, drawn over everything else
+    pub fn draw_gizmo<'d>(&'d self, drawable: &mut impl DrawSurface<'d>)
+    where 'a: 'd
+    {
+        let (width, height) = drawable.size();
+
+        //lengths are in screen heights so the gizmo doesnt get squished
+        let x_scale = height as f64 / width as f64;
+
+        let origin = Point2D{x: GIZMO_LENGTH * 1.5 * x_scale, y: GIZMO_LENGTH * 1.5};
+
+        let rotation = self.camera.view_matrix() * self.transform.orientation().to_mat4x4();
+
+        let axes = [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0]];
+
+        let mut axes: Vec<_> = axes.into_iter().map(|axis| rotation * axis)
+            .zip(self.gizmo_shaders.iter())
+            .collect();
+
+        //axes pointing at the camera get drawn last so they end up on top
+        axes.sort_by(|(a, _), (b, _)| a[2].total_cmp(&b[2]));
+
+        let previous_depth_func = drawable.depth_func();
+        drawable.set_depth_func(DepthFunc::Always);
+
+        for (direction, shader) in axes
+        {
+            let point = |x: f64, y: f64|
+            {
+                let mut interpolated = INTERPOLATED_ZEROS;
+
+                interpolated[ShaderValue::Depth as usize] = -1.0;
+                interpolated[ShaderValue::ColorR as usize] = 1.0;
+                interpolated[ShaderValue::ColorG as usize] = 1.0;
+                interpolated[ShaderValue::ColorB as usize] = 1.0;
+
+                Point{x, y, interpolated}
+            };
+
+            let end = Point2D{
+                x: origin.x + direction[0] * GIZMO_LENGTH * x_scale,
+                y: origin.y + direction[1] * GIZMO_LENGTH
+            };
+
+            drawable.line(point(origin.x, origin.y), point(end.x, end.y), shader);
+        }
+
+        drawable.set_depth_func(previous_depth_func);
     }
 }
 
@@ -334,4 +1032,279 @@ impl<'a> DerefMut for Object<'a>
     {
         &mut self.transform
     }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn flat_object<'a>(model: &'a Model, camera: &'a Camera, settings: &'a ShaderSettings) -> Object<'a>
+    {
+        let transform = Transform::new((0.0, 0.0, 0.0), (1.0, 1.0, 1.0), 0.0, (0.0, 1.0, 0.0));
+
+        let mut object = Object::new(model, transform, camera, &[], settings);
+        object.set_culling(false);
+
+        object
+    }
+
+    fn draw_stats(object: &Object) -> RenderStats
+    {
+        let mut drawable = DeferredDrawable::new((16, 16), BufferDisplay::new());
+
+        let mut drawable = &mut drawable;
+        let mut surface = drawable.surface();
+
+        let stats = object.draw(&mut surface);
+
+        surface.display();
+
+        stats
+    }
+
+    #[test]
+    fn near_clipping()
+    {
+        //a triangle on the ground reaching from in front of the camera to behind it
+        let model = Model::read_obj_str("v -1 -1 -5\nv 1 -1 -5\nv 0 -1 5\nf 1 2 3").unwrap();
+        let camera = Camera::new(0.1, 100.0, 1.0, 1.0);
+        let settings = ShaderSettings::default();
+
+        let object = flat_object(&model, &camera, &settings);
+
+        let view_points = [object.view_points[0], object.view_points[1], object.view_points[2]];
+        let corners = [0, 1, 2].map(|index|
+        {
+            let point = object.points[index];
+
+            let mut interpolated = INTERPOLATED_ZEROS;
+            interpolated[ShaderValue::Depth as usize] = point.z;
+
+            Point{x: point.x, y: point.y, interpolated}
+        });
+
+        let clipped = object.clip_near(view_points, corners);
+        assert_eq!(clipped.len(), 4);
+
+        //the new points sit right on the near plane
+        for point in &clipped[2..]
+        {
+            let depth = point.get(ShaderValue::Depth);
+            assert!((depth + 1.0).abs() < 0.0001, "{depth}");
+            assert!(point.x.is_finite() && point.y.is_finite());
+        }
+
+        let stats = draw_stats(&object);
+        assert_eq!((stats.triangles_drawn, stats.frustum_culled), (1, 0));
+    }
+
+    #[test]
+    fn behind_camera_culled()
+    {
+        let model = Model::read_obj_str("v -1 -1 2\nv 1 -1 2\nv 0 1 2\nf 1 2 3").unwrap();
+        let camera = Camera::new(0.1, 100.0, 1.0, 1.0);
+        let settings = ShaderSettings::default();
+
+        let object = flat_object(&model, &camera, &settings);
+
+        let stats = draw_stats(&object);
+        assert_eq!((stats.triangles_drawn, stats.frustum_culled), (0, 1));
+    }
+
+    #[test]
+    fn gizmo_keeps_depth_func()
+    {
+        let model = Model::read_obj_str("v -1 -1 -3\nv 1 -1 -3\nv 0 1 -3\nf 1 2 3").unwrap();
+        let camera = Camera::new(0.1, 100.0, 1.0, 1.0);
+        let settings = ShaderSettings::default();
+
+        let object = flat_object(&model, &camera, &settings);
+
+        let mut drawable = NormalDrawable::new((32, 32), BufferDisplay::new());
+
+        let mut drawable = &mut drawable;
+        let mut surface = drawable.surface();
+
+        surface.set_depth_func(DepthFunc::LessEqual);
+
+        object.draw_gizmo(&mut surface);
+
+        assert!(surface.pixels_shaded() > 0);
+        assert_eq!(surface.depth_func(), DepthFunc::LessEqual);
+    }
+
+    #[test]
+    fn oit_skips_prepass()
+    {
+        let mut model = Model::read_obj_str("v -1 -1 -3\nv 1 -1 -3\nv 0 1 -3\nf 1 2 3").unwrap();
+
+        let mut material = model::Material::new();
+        material.opacity = 0.5;
+
+        model.materials.push(material);
+        model.material_indices = vec![Some(0)];
+
+        let camera = Camera::new(0.1, 100.0, 1.0, 1.0);
+        let settings = ShaderSettings::default();
+
+        let object = flat_object(&model, &camera, &settings);
+
+        let mut drawable = DeferredDrawable::new((16, 16), BufferDisplay::new());
+        drawable.set_oit(true);
+
+        let mut drawable = &mut drawable;
+        let mut surface = drawable.surface();
+
+        //a second pass would push every see-through fragment again
+        assert!(!surface.uses_passes());
+
+        object.draw(&mut surface);
+
+        assert!(surface.pixels_shaded() > 0);
+
+        let mut normal = NormalDrawable::new((16, 16), BufferDisplay::new());
+
+        let mut normal = &mut normal;
+        assert!(normal.surface().uses_passes());
+    }
+
+    fn view_point(camera: &Camera, point: Point3D) -> Point3D
+    {
+        let point = camera.view_matrix() * [point.x, point.y, point.z, 1.0];
+
+        Point3D{x: point[0], y: point[1], z: point[2]}
+    }
+
+    #[test]
+    fn screen_triangles()
+    {
+        let settings = ShaderSettings::default();
+
+        let mut shader = FaceShader::new(Color::new(1.0, 0.0, 0.0), &[], &settings);
+        shader.unlit = true;
+
+        let point = |x: f64, y: f64, depth: f64|
+        {
+            let mut interpolated = INTERPOLATED_ZEROS;
+            interpolated[ShaderValue::Depth as usize] = depth;
+            interpolated[ShaderValue::ColorR as usize] = 1.0;
+            interpolated[ShaderValue::ColorG as usize] = 1.0;
+            interpolated[ShaderValue::ColorB as usize] = 1.0;
+
+            Point{x, y, interpolated}
+        };
+
+        //bottom left half of the screen, the part past the far plane gets cut off
+        let triangle = [point(0.0, 0.0, 0.0), point(1.0, 0.0, 0.0), point(0.0, 1.0, 1.6)];
+
+        let colors = render_triangles_to_buffer(&[(triangle, &shader)], (4, 4));
+
+        let red = |x: usize, y: usize| colors[y * 4 + x].r == 1.0;
+
+        //rows go from the top down in the buffer
+        assert!(red(0, 3) && red(1, 3) && red(0, 2) && red(1, 2));
+        assert!(!red(3, 2) && !red(3, 0));
+
+        //the depth reaches 1 in the third row from the bottom
+        assert!(!red(0, 1));
+    }
+
+    #[test]
+    fn rasterizers_match()
+    {
+        use crate::renderer::normal_drawable::drawable::Rasterizer;
+
+        let settings = ShaderSettings::default();
+
+        let mut shader = FaceShader::new(Color::new(1.0, 1.0, 1.0), &[], &settings);
+        shader.unlit = true;
+
+        let point = |x: f64, y: f64|
+        {
+            let mut interpolated = INTERPOLATED_ZEROS;
+            interpolated[ShaderValue::ColorR as usize] = 1.0;
+            interpolated[ShaderValue::ColorG as usize] = 1.0;
+            interpolated[ShaderValue::ColorB as usize] = 1.0;
+
+            Point{x, y, interpolated}
+        };
+
+        let triangles = [
+            [point(0.1, 0.1), point(0.9, 0.3), point(0.4, 0.95)],
+            [point(0.9, 0.3), point(0.95, 0.9), point(0.4, 0.95)]
+        ];
+
+        let size = (64, 64);
+
+        let render = |rasterizer|
+        {
+            let mut drawable = DeferredDrawable::new(size, BufferDisplay::new());
+            drawable.set_rasterizer(rasterizer);
+
+            {
+                let mut drawable = &mut drawable;
+                let mut surface = drawable.surface();
+
+                draw_triangles(&mut surface, &triangles, &shader);
+
+                surface.display();
+            }
+
+            drawable.into_display().coverage().to_vec()
+        };
+
+        let scanline = render(Rasterizer::Scanline);
+        let barycentric = render(Rasterizer::Barycentric);
+
+        let covered = barycentric.iter().filter(|covered| **covered).count();
+        let different = scanline.iter().zip(barycentric.iter()).filter(|(a, b)| a != b).count();
+
+        //they only get to disagree about which pixels along the edges count
+        assert!(covered > 1000, "{covered}");
+        assert!(different < covered / 10, "{different} of {covered}");
+    }
+
+    #[test]
+    fn camera_roll()
+    {
+        let eye = Point3D{x: 1.0, y: 2.0, z: 3.0};
+        let target = Point3D{x: 1.0, y: 2.0, z: -7.0};
+        let up = Point3D{x: 0.0, y: 1.0, z: 0.0};
+
+        let mut camera = Camera::new(0.1, 100.0, 1.0, 1.0);
+        camera.look_at(eye, target, up, 90.0_f64.to_radians());
+
+        //still looking at the same thing
+        let center = view_point(&camera, target);
+        assert!(center.x.abs() < 0.0001 && center.y.abs() < 0.0001, "{center:?}");
+        assert!((center.z + 10.0).abs() < 0.0001, "{center:?}");
+
+        //tilted to the right so whatever was on the right is now on top
+        let right = view_point(&camera, target + Point3D{x: 1.0, y: 0.0, z: 0.0});
+        assert!(right.x.abs() < 0.0001 && (right.y - 1.0).abs() < 0.0001, "{right:?}");
+
+        //rolling afterwards has to land on the same view for any direction
+        let target = Point3D{x: 4.0, y: -1.0, z: 0.5};
+        let roll = 0.7;
+
+        let mut rolled = Camera::new(0.1, 100.0, 1.0, 1.0);
+        rolled.look_at(eye, target, up, roll);
+
+        let mut composed = Camera::new(0.1, 100.0, 1.0, 1.0);
+        composed.look_at(eye, target, up, 0.0);
+        composed.roll(roll);
+
+        for y in 0..4
+        {
+            for x in 0..4
+            {
+                let (a, b) = (rolled.view_matrix().mat[y][x], composed.view_matrix().mat[y][x]);
+                assert!((a - b).abs() < 0.0001, "{a} {b}");
+            }
+        }
+
+        let center = view_point(&rolled, target);
+        assert!(center.x.abs() < 0.0001 && center.y.abs() < 0.0001, "{center:?}");
+    }
 }
\ No newline at end of file
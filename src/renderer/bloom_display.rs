@@ -0,0 +1,131 @@
+use crate::renderer::common::{Color, Tonemap};
+use crate::renderer::normal_drawable::DrawableDisplay;
+
+
+#[derive(Debug, Clone, Copy)]
+pub struct Bloom
+{
+    pub threshold: f64,
+    pub intensity: f64
+}
+
+impl Bloom
+{
+    //the glow reaches about the same part of the picture at any resolution
+    fn kernel(size: (usize, usize)) -> Vec<f64>
+    {
+        let sigma = (size.0.min(size.1) as f64 / 100.0).max(1.0);
+        let radius = (sigma * 3.0).ceil() as isize;
+
+        let weights = (-radius..=radius).map(|offset|
+        {
+            (-((offset * offset) as f64) / (2.0 * sigma * sigma)).exp()
+        }).collect::<Vec<f64>>();
+
+        let total: f64 = weights.iter().sum();
+
+        weights.into_iter().map(|weight| weight / total).collect()
+    }
+
+    fn blur_pass(size: (usize, usize), colors: &[Color], kernel: &[f64], horizontal: bool) -> Vec<Color>
+    {
+        let (width, height) = size;
+        let radius = (kernel.len() / 2) as isize;
+
+        (0..width * height).map(|index|
+        {
+            let x = (index % width) as isize;
+            let y = (index / width) as isize;
+
+            kernel.iter().enumerate().fold(Color::new(0.0, 0.0, 0.0), |total, (offset, weight)|
+            {
+                let offset = offset as isize - radius;
+
+                //the edges repeat outwards
+                let (sx, sy) = if horizontal
+                {
+                    ((x + offset).clamp(0, width as isize - 1), y)
+                } else
+                {
+                    (x, (y + offset).clamp(0, height as isize - 1))
+                };
+
+                total + colors[sy as usize * width + sx as usize] * *weight
+            })
+        }).collect()
+    }
+
+    pub fn apply(&self, size: (usize, usize), colors: &[Color]) -> Vec<Color>
+    {
+        let black = Color::new(0.0, 0.0, 0.0);
+
+        let bright = colors.iter().map(|color|
+        {
+            if color.luminance() > self.threshold
+            {
+                *color
+            } else
+            {
+                black
+            }
+        }).collect::<Vec<Color>>();
+
+        let kernel = Self::kernel(size);
+
+        let blurred = Self::blur_pass(size, &bright, &kernel, true);
+        let blurred = Self::blur_pass(size, &blurred, &kernel, false);
+
+        colors.iter().zip(blurred).map(|(color, glow)| *color + glow * self.intensity).collect()
+    }
+}
+
+pub struct BloomDisplay<D>
+{
+    display: D,
+    bloom: Bloom,
+    tonemap: Option<Tonemap>
+}
+
+impl<D> BloomDisplay<D>
+{
+    pub fn new(display: D, bloom: Bloom) -> Self
+    {
+        Self{display, bloom, tonemap: None}
+    }
+
+    //the shaders skip tonemapping with bloom so the bright pass sees the real brightness
+    pub fn set_tonemap(&mut self, tonemap: Option<Tonemap>)
+    {
+        self.tonemap = tonemap;
+    }
+}
+
+impl<D: DrawableDisplay> DrawableDisplay for BloomDisplay<D>
+{
+    fn prepare(&mut self, size: (usize, usize))
+    {
+        self.display.prepare(size);
+    }
+
+    fn display(&mut self, size: (usize, usize), colors: &[Color], coverage: &[bool])
+    {
+        let colors = self.bloom.apply(size, colors);
+
+        let colors = match self.tonemap
+        {
+            Some(tonemap) => colors.into_iter().zip(coverage).map(|(color, covered)|
+            {
+                if *covered
+                {
+                    tonemap.apply(color)
+                } else
+                {
+                    color
+                }
+            }).collect(),
+            None => colors
+        };
+
+        self.display.display(size, &colors, coverage);
+    }
+}
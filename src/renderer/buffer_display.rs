@@ -0,0 +1,58 @@
+use crate::renderer::common::Color;
+use crate::renderer::normal_drawable::DrawableDisplay;
+
+
+//keeps the last displayed frame around instead of outputting it anywhere
+#[derive(Debug, Clone, Default)]
+pub struct BufferDisplay
+{
+    size: (usize, usize),
+    colors: Vec<Color>,
+    coverage: Vec<bool>
+}
+
+#[allow(dead_code)]
+impl BufferDisplay
+{
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    pub fn size(&self) -> (usize, usize)
+    {
+        self.size
+    }
+
+    pub fn colors(&self) -> &[Color]
+    {
+        &self.colors
+    }
+
+    pub fn coverage(&self) -> &[bool]
+    {
+        &self.coverage
+    }
+
+    pub fn into_colors(self) -> Vec<Color>
+    {
+        self.colors
+    }
+}
+
+impl DrawableDisplay for BufferDisplay
+{
+    fn prepare(&mut self, size: (usize, usize))
+    {
+        self.size = size;
+    }
+
+    fn display(&mut self, _: (usize, usize), colors: &[Color], coverage: &[bool])
+    {
+        self.colors.clear();
+        self.colors.extend_from_slice(colors);
+
+        self.coverage.clear();
+        self.coverage.extend_from_slice(coverage);
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     f64,
-    ops::{Sub, Mul, Neg}
+    ops::{Add, Sub, Mul, Div, Neg}
 };
 
 pub use texture::*;
@@ -9,6 +9,9 @@ pub mod texture;
 pub use matrices::*;
 pub mod matrices;
 
+pub use quaternion::*;
+pub mod quaternion;
+
 
 #[derive(Debug, Clone, Copy)]
 pub struct Color
@@ -38,6 +41,117 @@ impl Color
     {
         p0 * (1.0 - a) + p1 * a
     }
+
+    pub fn saturate(self) -> Self
+    {
+        Color{
+            r: self.r.clamp(0.0, 1.0),
+            g: self.g.clamp(0.0, 1.0),
+            b: self.b.clamp(0.0, 1.0)
+        }
+    }
+
+    //rec 709 weights, green looks way brighter than blue
+    pub fn luminance(self) -> f64
+    {
+        self.r * 0.2126 + self.g * 0.7152 + self.b * 0.0722
+    }
+}
+
+impl Add for Color
+{
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output
+    {
+        Color{
+            r: self.r + other.r,
+            g: self.g + other.g,
+            b: self.b + other.b
+        }
+    }
+}
+
+impl Mul<f64> for Color
+{
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output
+    {
+        Color{
+            r: self.r * rhs,
+            g: self.g * rhs,
+            b: self.b * rhs
+        }
+    }
+}
+
+impl Mul for Color
+{
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output
+    {
+        Color{
+            r: self.r * rhs.r,
+            g: self.g * rhs.g,
+            b: self.b * rhs.b
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gamma
+{
+    Srgb,
+    Power(f64)
+}
+
+impl Gamma
+{
+    pub fn encode(self, value: f64) -> f64
+    {
+        let value = value.clamp(0.0, 1.0);
+
+        match self
+        {
+            Gamma::Srgb =>
+            {
+                if value <= 0.0031308
+                {
+                    value * 12.92
+                } else
+                {
+                    1.055 * value.powf(1.0 / 2.4) - 0.055
+                }
+            },
+            Gamma::Power(gamma) => value.powf(1.0 / gamma)
+        }
+    }
+
+    //saturates so values over 1 (hdr textures, highlights) dont wrap around
+    pub fn encode_u8(self, value: f64) -> u8
+    {
+        (self.encode(value).clamp(0.0, 1.0) * u8::MAX as f64) as u8
+    }
+
+    pub fn decode(self, value: f64) -> f64
+    {
+        match self
+        {
+            Gamma::Srgb =>
+            {
+                if value <= 0.04045
+                {
+                    value / 12.92
+                } else
+                {
+                    ((value + 0.055) / 1.055).powf(2.4)
+                }
+            },
+            Gamma::Power(gamma) => value.max(0.0).powf(gamma)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -47,6 +161,45 @@ pub struct Point2D<T=f64>
     pub y: T
 }
 
+impl Add for Point2D<f64>
+{
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output
+    {
+        Self{
+            x: self.x + other.x,
+            y: self.y + other.y
+        }
+    }
+}
+
+impl Sub for Point2D<f64>
+{
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output
+    {
+        Self{
+            x: self.x - other.x,
+            y: self.y - other.y
+        }
+    }
+}
+
+impl Mul<f64> for Point2D<f64>
+{
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output
+    {
+        Self{
+            x: self.x * rhs,
+            y: self.y * rhs
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Point3D
 {
@@ -55,17 +208,27 @@ pub struct Point3D
     pub z: f64
 }
 
+#[allow(dead_code)]
 impl Point3D
 {
     pub fn normalized(self) -> Self
     {
-        let magnitude = (self.x*self.x + self.y*self.y + self.z*self.z).sqrt();
+        self / self.length()
+    }
+
+    pub fn length(self) -> f64
+    {
+        self.dot(self).sqrt()
+    }
 
-        let x = self.x / magnitude;
-        let y = self.y / magnitude;
-        let z = self.z / magnitude;
+    pub fn distance(self, other: Self) -> f64
+    {
+        (self - other).length()
+    }
 
-        Self{x, y, z}
+    pub fn lerp(self, other: Self, a: f64) -> Self
+    {
+        self * (1.0 - a) + other * a
     }
 
     pub fn dot(self, other: Self) -> f64
@@ -102,6 +265,34 @@ impl Mul<f64> for Point3D
     }
 }
 
+impl Div<f64> for Point3D
+{
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self::Output
+    {
+        Self{
+            x: self.x / rhs,
+            y: self.y / rhs,
+            z: self.z / rhs
+        }
+    }
+}
+
+impl Add for Point3D
+{
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output
+    {
+        Self{
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z
+        }
+    }
+}
+
 impl Sub for Point3D
 {
     type Output = Self;
@@ -130,6 +321,9 @@ impl Neg for Point3D
     }
 }
 
+//the layout of every interpolated array, including the ones in the deferred g-buffer
+//depth is in ndc (-1 to 1), position and normal are in world space, color is the vertex color
+#[allow(clippy::upper_case_acronyms)]
 pub enum ShaderValue
 {
     Depth = 0,
@@ -141,6 +335,9 @@ pub enum ShaderValue
     NormalZ,
     UvX,
     UvY,
+    ColorR,
+    ColorG,
+    ColorB,
     LAST
 }
 
@@ -158,17 +355,21 @@ impl IValue
         IValue{lower: self.upper, upper: self.lower}
     }
 
-    fn interpolator(&self, amount: usize) -> IValueIter
+    fn interpolator(&self, steps: usize) -> IValueIter
     {
-        IValueIter{start: self.lower, step: (self.upper - self.lower) / amount as f64}
+        IValueIter{lower: self.lower, upper: self.upper, steps: steps.max(1), index: 0}
     }
 }
 
+//every value is computed from its index instead of adding up steps, so long spans dont drift
+//and the last one lands exactly on the upper value
 #[derive(Debug, Clone)]
 struct IValueIter
 {
-    start: f64,
-    step: f64
+    lower: f64,
+    upper: f64,
+    steps: usize,
+    index: usize
 }
 
 impl Iterator for IValueIter
@@ -177,10 +378,10 @@ impl Iterator for IValueIter
 
     fn next(&mut self) -> Option<Self::Item>
     {
-        let value = self.start;
-        self.start += self.step;
+        let amount = self.index as f64 / self.steps as f64;
+        self.index += 1;
 
-        Some(value)
+        Some(self.lower * (1.0 - amount) + self.upper * amount)
     }
 }
 
@@ -236,10 +437,11 @@ impl Interpolator
             .collect::<Vec<IValue>>().try_into().expect("same amount of values just reversed"))
     }
 
-    pub fn interpolator(&self, amount: usize) -> InterpolaterIter
+    //goes from the lower to the upper values in this many steps (so one more value than steps)
+    pub fn interpolator(&self, steps: usize) -> InterpolaterIter
     {
         InterpolaterIter{
-            values: self.values.iter().map(|value| value.interpolator(amount + 1))
+            values: self.values.iter().map(|value| value.interpolator(steps))
                 .collect::<Vec<IValueIter>>().try_into().expect("it contains same amount")
         }
     }
@@ -263,12 +465,331 @@ impl Iterator for InterpolaterIter
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Visualization
+{
+    Normals
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tonemap
+{
+    Reinhard
+}
+
+impl Tonemap
+{
+    pub fn apply(self, color: Color) -> Color
+    {
+        match self
+        {
+            Tonemap::Reinhard =>
+            {
+                let map = |value: f64| value / (1.0 + value);
+
+                Color::new(map(color.r), map(color.g), map(color.b))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Fog
+{
+    pub color: Color,
+    pub start: f64,
+    pub end: f64
+}
+
+impl Fog
+{
+    //depth is the distance in front of the camera
+    pub fn apply(&self, color: Color, depth: f64) -> Color
+    {
+        let amount = if self.end > self.start
+        {
+            ((depth - self.start) / (self.end - self.start)).clamp(0.0, 1.0)
+        } else if depth >= self.end
+        {
+            1.0
+        } else
+        {
+            0.0
+        };
+
+        color.lerp(&self.color, amount)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DepthOfField
+{
+    pub focus: f64,
+    //blur radius in pixels for something infinitely far away
+    pub aperture: f64,
+    pub near: f64,
+    pub far: f64
+}
+
+impl DepthOfField
+{
+    //the depth buffer holds the projected depth, this turns it back into a distance
+    pub fn distance(&self, depth: f64) -> f64
+    {
+        let (near, far) = (self.near, self.far);
+
+        2.0 * near * far / (far + near - depth * (far - near))
+    }
+
+    fn radius(&self, depth: f64) -> f64
+    {
+        let distance = self.distance(depth);
+
+        //things right in front of the lens would blur over the whole image
+        (self.aperture * (distance - self.focus).abs() / distance).min(self.aperture * 2.0)
+    }
+
+    //every pixel averages a circle sized by how far it is from the focal plane,
+    //the background gets filled in later so it neither blurs nor gets blurred into
+    pub fn blur(&self, size: (usize, usize), colors: &[Color], depths: &[f64], coverage: &[bool]) -> Vec<Color>
+    {
+        let (width, height) = size;
+
+        (0..width * height).map(|index|
+        {
+            if !coverage[index]
+            {
+                return colors[index];
+            }
+
+            let radius = self.radius(depths[index]);
+            if radius < 0.5
+            {
+                return colors[index];
+            }
+
+            let x = (index % width) as isize;
+            let y = (index / width) as isize;
+
+            let reach = radius.ceil() as isize;
+
+            let mut total = Color::new(0.0, 0.0, 0.0);
+            let mut count = 0;
+
+            for oy in -reach..=reach
+            {
+                for ox in -reach..=reach
+                {
+                    let (sx, sy) = (x + ox, y + oy);
+                    if sx < 0 || sy < 0 || sx >= width as isize || sy >= height as isize
+                    {
+                        continue;
+                    }
+
+                    let sample = sy as usize * width + sx as usize;
+                    if ((ox * ox + oy * oy) as f64) > radius * radius || !coverage[sample]
+                    {
+                        continue;
+                    }
+
+                    total = total + colors[sample];
+                    count += 1;
+                }
+            }
+
+            total * (1.0 / count as f64)
+        }).collect()
+    }
+}
+
+//a rectangle of pixels, y goes from the top down like in the final image and the ends arent included
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Region
+{
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize
+}
+
+impl Region
+{
+    pub fn contains(&self, x: usize, y: usize) -> bool
+    {
+        (self.x0..self.x1).contains(&x) && (self.y0..self.y1).contains(&y)
+    }
+
+    pub fn size(&self) -> (usize, usize)
+    {
+        (self.x1.saturating_sub(self.x0), self.y1.saturating_sub(self.y0))
+    }
+
+    //the same part of an image thats a different size, like a preview or a supersampled one
+    pub fn scaled(self, from: (usize, usize), to: (usize, usize)) -> Self
+    {
+        //rounds outwards so no pixel that was partly inside gets lost
+        let start = |value: usize, from: usize, to: usize| (value * to / from.max(1)).min(to);
+        let end = |value: usize, from: usize, to: usize| (value * to).div_ceil(from.max(1)).min(to);
+
+        Region{
+            x0: start(self.x0, from.0, to.0),
+            y0: start(self.y0, from.1, to.1),
+            x1: end(self.x1, from.0, to.0),
+            y1: end(self.y1, from.1, to.1)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Outline
+{
+    pub thickness: usize,
+    pub color: Color,
+    //only this object gets outlined, everything else counts as background
+    pub object: Option<usize>
+}
+
+impl Outline
+{
+    //edges are one pixel wide, the thickness grows them in every direction
+    pub fn apply(&self, size: (usize, usize), edges: &[bool], colors: &mut [Color], coverage: &mut [bool])
+    {
+        let (width, height) = size;
+        let reach = self.thickness.saturating_sub(1) as isize;
+
+        for index in 0..width * height
+        {
+            let x = (index % width) as isize;
+            let y = (index / width) as isize;
+
+            let near_edge = (-reach..=reach).any(|oy|
+            {
+                (-reach..=reach).any(|ox|
+                {
+                    let (sx, sy) = (x + ox, y + oy);
+
+                    sx >= 0 && sy >= 0 && sx < width as isize && sy < height as isize
+                        && edges[sy as usize * width + sx as usize]
+                })
+            });
+
+            if near_edge
+            {
+                colors[index] = self.color;
+                coverage[index] = true;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShaderSettings
+{
+    pub visualization: Option<Visualization>,
+    pub ambient: f64,
+    pub tonemap: Option<Tonemap>,
+    pub fog: Option<Fog>,
+    pub double_sided: bool,
+    pub environment: Option<Texture>,
+    //overrides the reflectivity of every material
+    pub reflectivity: Option<f64>,
+    //multiplies the highlights, 0 leaves just the diffuse part
+    pub specular: f64,
+    pub diffuse: f64,
+    //whatever gets reflected as a highlight isnt there for the diffuse part anymore
+    pub energy_conserving: bool,
+    //replaces all the lighting with a lookup by the view space normal
+    pub matcap: Option<Texture>,
+    //how many flat steps the lighting gets rounded to
+    pub cel_bands: Option<usize>
+}
+
+impl Default for ShaderSettings
+{
+    fn default() -> Self
+    {
+        ShaderSettings{
+            visualization: None,
+            ambient: 0.2,
+            tonemap: None,
+            fog: None,
+            double_sided: false,
+            environment: None,
+            reflectivity: None,
+            specular: 1.0,
+            diffuse: 1.0,
+            energy_conserving: false,
+            matcap: None,
+            cel_bands: None
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FaceShader<'a>
 {
     pub color: Color,
     pub lights: &'a [Light],
-    pub texture: Option<&'a Texture>
+    pub camera_position: Point3D,
+    pub camera_view: Mat4x4,
+    pub texture: Option<&'a Texture>,
+    pub emissive: Option<Color>,
+    pub emissive_texture: Option<&'a Texture>,
+    pub shininess: f64,
+    //scales the shininess per pixel, white keeps all of it
+    pub shininess_texture: Option<&'a Texture>,
+    pub double_sided: bool,
+    pub reflectivity: f64,
+    //only blended with order independent transparency, otherwise everything is opaque
+    pub opacity: f64,
+    //just the flat color, for overlays
+    pub unlit: bool,
+    pub id: PixelId,
+    pub settings: &'a ShaderSettings
+}
+
+//used by materials without an Ns
+pub const DEFAULT_SHININESS: f64 = 32.0;
+
+impl<'a> FaceShader<'a>
+{
+    //a plain lit color seen from a camera at the origin, the fields can be changed after
+    pub fn new(color: Color, lights: &'a [Light], settings: &'a ShaderSettings) -> Self
+    {
+        FaceShader{
+            color,
+            lights,
+            camera_position: Point3D{x: 0.0, y: 0.0, z: 0.0},
+            camera_view: Mat4x4::new(),
+            texture: None,
+            emissive: None,
+            emissive_texture: None,
+            shininess: DEFAULT_SHININESS,
+            shininess_texture: None,
+            double_sided: false,
+            reflectivity: 0.0,
+            opacity: 1.0,
+            unlit: false,
+            id: PixelId{object: 0, material: None},
+            settings
+        }
+    }
+}
+
+//which object and material a pixel came from, for masking post processing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelId
+{
+    pub object: usize,
+    pub material: Option<usize>
+}
+
+#[derive(Debug, Clone)]
+pub enum LightKind
+{
+    Point,
+    //angles are in radians from the direction to the edge of the cone
+    Spot{direction: Point3D, inner_angle: f64, outer_angle: f64}
 }
 
 #[derive(Debug, Clone)]
@@ -276,7 +797,35 @@ pub struct Light
 {
     pub position: Point3D,
     //pub color: Color, no colored lights >:(
-    pub intensity: f64
+    pub intensity: f64,
+    pub kind: LightKind
+}
+
+impl Light
+{
+    pub fn attenuation(&self, light_direction: Point3D) -> f64
+    {
+        match &self.kind
+        {
+            LightKind::Point => 1.0,
+            LightKind::Spot{direction, inner_angle, outer_angle} =>
+            {
+                let cos_angle = direction.normalized().dot(-light_direction);
+
+                let inner = inner_angle.cos();
+                let outer = outer_angle.cos();
+
+                if inner <= outer
+                {
+                    return if cos_angle >= outer {1.0} else {0.0};
+                }
+
+                let a = ((cos_angle - outer) / (inner - outer)).clamp(0.0, 1.0);
+
+                a * a * (3.0 - 2.0 * a)
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -336,18 +885,135 @@ mod tests
         let amount = 8;
         let mut interpolator = interpolator.interpolator(amount);
 
-        for a in 0..amount
+        for a in 0..=amount
         {
             let values = interpolator.next().unwrap();
 
             let iter = values0.iter().zip(values1.iter()).zip(values.iter());
             for ((value0, value1), test_value) in iter
             {
-                let a = a as f64 / (amount + 1) as f64;
+                let a = a as f64 / amount as f64;
                 let correct = value0 * (1.0 - a) + value1 * a;
 
                 assert!(close_enough(correct, *test_value));
             }
         }
     }
+
+    #[test]
+    fn long_span_endpoint()
+    {
+        let mut values0 = INTERPOLATED_ZEROS;
+        values0[0] = 0.1;
+
+        let mut values1 = INTERPOLATED_ZEROS;
+        values1[0] = 1234.567;
+
+        let steps = 100_000;
+        let last = Interpolator::new(combine_interpolated(values0, values1))
+            .interpolator(steps)
+            .nth(steps)
+            .unwrap();
+
+        //adding up the step every pixel ends up way further off than this
+        assert!((last[0] - values1[0]).abs() < 1e-12, "{}", last[0]);
+    }
+
+    #[test]
+    fn point_arithmetic()
+    {
+        let a = Point3D{x: 1.0, y: 2.0, z: 3.0};
+        let b = Point3D{x: 4.0, y: -2.0, z: 0.5};
+
+        let sum = a + b;
+        assert!(close_enough(sum.x, 5.0));
+        assert!(close_enough(sum.y, 0.0));
+        assert!(close_enough(sum.z, 3.5));
+
+        let halved = a / 2.0;
+        assert!(close_enough(halved.x, 0.5));
+        assert!(close_enough(halved.y, 1.0));
+        assert!(close_enough(halved.z, 1.5));
+
+        assert!(close_enough(Point3D{x: 3.0, y: 4.0, z: 0.0}.length(), 5.0));
+        assert!(close_enough(a.normalized().length(), 1.0));
+
+        assert!(close_enough(a.distance(b), (9.0_f64 + 16.0 + 6.25).sqrt()));
+        assert!(close_enough(a.distance(a), 0.0));
+    }
+
+    #[test]
+    fn point_lerp()
+    {
+        let a = Point3D{x: -1.0, y: 2.0, z: 4.0};
+        let b = Point3D{x: 3.0, y: 0.0, z: 5.0};
+
+        let start = a.lerp(b, 0.0);
+        assert!(close_enough(start.distance(a), 0.0));
+
+        let end = a.lerp(b, 1.0);
+        assert!(close_enough(end.distance(b), 0.0));
+
+        let middle = a.lerp(b, 0.5);
+        assert!(close_enough(middle.x, 1.0));
+        assert!(close_enough(middle.y, 1.0));
+        assert!(close_enough(middle.z, 4.5));
+    }
+
+    #[test]
+    fn point2d_arithmetic()
+    {
+        let a = Point2D{x: 0.25, y: 1.0};
+        let b = Point2D{x: 0.5, y: -0.5};
+
+        let sum = a + b;
+        assert!(close_enough(sum.x, 0.75));
+        assert!(close_enough(sum.y, 0.5));
+
+        let difference = a - b;
+        assert!(close_enough(difference.x, -0.25));
+        assert!(close_enough(difference.y, 1.5));
+
+        let scaled = a * 4.0;
+        assert!(close_enough(scaled.x, 1.0));
+        assert!(close_enough(scaled.y, 4.0));
+
+        //lerp written with the new ops hits both ends and the middle
+        let lerp = |t: f64| a * (1.0 - t) + b * t;
+        assert!(close_enough(lerp(0.0).x, a.x) && close_enough(lerp(0.0).y, a.y));
+        assert!(close_enough(lerp(1.0).x, b.x) && close_enough(lerp(1.0).y, b.y));
+        assert!(close_enough(lerp(0.5).x, 0.375) && close_enough(lerp(0.5).y, 0.25));
+    }
+
+    #[test]
+    fn depth_of_field_focal_plane()
+    {
+        let depth_of_field = DepthOfField{focus: 5.0, aperture: 4.0, near: 0.1, far: 100.0};
+
+        //the projected depth that turns back into the focal distance
+        let (near, far) = (depth_of_field.near, depth_of_field.far);
+        let focal_depth = (far + near - 2.0 * near * far / depth_of_field.focus) / (far - near);
+
+        assert!(close_enough(depth_of_field.distance(focal_depth), 5.0));
+
+        let size = (5, 5);
+        let colors = (0..25).map(|index| Color::new((index % 2) as f64, 0.0, 0.0)).collect::<Vec<_>>();
+
+        //a sharp pixel in the middle of a blurry patch, with an uncovered corner
+        let mut depths = vec![0.99; 25];
+        depths[12] = focal_depth;
+
+        let mut coverage = vec![true; 25];
+        coverage[0] = false;
+
+        let blurred = depth_of_field.blur(size, &colors, &depths, &coverage);
+
+        assert_eq!(blurred[12].r, colors[12].r);
+        assert_eq!(blurred[0].r, colors[0].r);
+        assert!(blurred[1].r > 0.0 && blurred[1].r < 1.0);
+
+        //only the covered pixels get averaged
+        let everything = depth_of_field.blur(size, &colors, &depths, &[true; 25]);
+        assert!(blurred[6].r != everything[6].r);
+    }
 }
\ No newline at end of file
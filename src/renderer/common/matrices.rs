@@ -23,6 +23,65 @@ impl Mat4x4
             [0.0, 0.0, 0.0, 1.0]
         ]}
     }
+
+    pub fn transpose(mut self) -> Self
+    {
+        for y in 0..4
+        {
+            for x in (y + 1)..4
+            {
+                (self.mat[y][x], self.mat[x][y]) = (self.mat[x][y], self.mat[y][x]);
+            }
+        }
+
+        self
+    }
+
+    //the 3x3 matrix left after removing a row and a column
+    fn minor(&self, row: usize, column: usize) -> Mat3x3
+    {
+        let mut out = Mat3x3{mat: [[0.0; 3]; 3]};
+
+        for (y, source_y) in (0..4).filter(|y| *y != row).enumerate()
+        {
+            for (x, source_x) in (0..4).filter(|x| *x != column).enumerate()
+            {
+                out.mat[y][x] = self.mat[source_y][source_x];
+            }
+        }
+
+        out
+    }
+
+    fn cofactor(&self, row: usize, column: usize) -> f64
+    {
+        let sign = if (row + column) % 2 == 0 {1.0} else {-1.0};
+
+        sign * self.minor(row, column).determinant()
+    }
+
+    pub fn determinant(&self) -> f64
+    {
+        (0..4).map(|x| self.mat[0][x] * self.cofactor(0, x)).sum()
+    }
+
+    pub fn inverse(self) -> Self
+    {
+        let inv_determinant = 1.0 / self.determinant();
+
+        let mut out = Self{mat: [[0.0; 4]; 4]};
+
+        //adjugate is the transposed cofactor matrix
+        for y in 0..4
+        {
+            for x in 0..4
+            {
+                out.mat[x][y] = self.cofactor(y, x) * inv_determinant;
+            }
+        }
+
+        out
+    }
 }
 
 impl Display for Mat4x4
@@ -47,7 +106,7 @@ impl Mul<[f64; 4]> for Mat4x4
         let mut out = [0.0; 4];
         for i in 0..4
         {
-            out[i] = self.mat[i].into_iter().zip(rhs.into_iter()).map(|(m, v)| m * v).sum();
+            out[i] = self.mat[i].into_iter().zip(rhs).map(|(m, v)| m * v).sum();
         }
 
         out
@@ -187,7 +246,7 @@ impl Mul<[f64; 3]> for Mat3x3
         let mut out = [0.0; 3];
         for i in 0..3
         {
-            out[i] = self.mat[i].into_iter().zip(rhs.into_iter()).map(|(m, v)| m * v).sum();
+            out[i] = self.mat[i].into_iter().zip(rhs).map(|(m, v)| m * v).sum();
         }
 
         out
@@ -225,4 +284,67 @@ impl From<Mat4x4> for Mat3x3
             [value.mat[2][0], value.mat[2][1], value.mat[2][2]]
         ]}
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn inverse_identity()
+    {
+        //small lcg so the test doesnt need a rng crate
+        let mut seed: u64 = 12345;
+        let mut random = ||
+        {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+
+            ((seed >> 33) as f64 / (1u64 << 31) as f64) * 2.0 - 1.0
+        };
+
+        for _ in 0..100
+        {
+            let mut m = Mat4x4::new();
+            for y in 0..3
+            {
+                for x in 0..4
+                {
+                    m.mat[y][x] = random() * 5.0;
+                }
+            }
+
+            if m.determinant().abs() < 0.01
+            {
+                continue;
+            }
+
+            let identity = Mat4x4::new();
+            let product = m * m.inverse();
+
+            for y in 0..4
+            {
+                for x in 0..4
+                {
+                    assert!((product.mat[y][x] - identity.mat[y][x]).abs() < 0.0001, "{product}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn transpose_determinant()
+    {
+        let mut m = Mat4x4::new();
+        m.mat[0][3] = 2.0;
+        m.mat[1][0] = 3.0;
+        m.mat[2][2] = 4.0;
+        m.mat[3][1] = 0.5;
+
+        let transposed = m.transpose();
+        assert_eq!(transposed.mat[3][0], 2.0);
+        assert_eq!(transposed.mat[0][1], 3.0);
+
+        assert!((m.determinant() - transposed.determinant()).abs() < 0.0001);
+    }
+}
@@ -0,0 +1,205 @@
+use std::ops::Mul;
+
+use super::{Point3D, Mat4x4};
+
+
+#[derive(Debug, Clone, Copy)]
+pub struct Quaternion
+{
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64
+}
+
+#[allow(dead_code)]
+impl Quaternion
+{
+    pub fn new() -> Self
+    {
+        Self{w: 1.0, x: 0.0, y: 0.0, z: 0.0}
+    }
+
+    pub fn from_axis_angle(axis: Point3D, angle: f64) -> Self
+    {
+        let axis = axis.normalized();
+
+        let half = angle / 2.0;
+        let s = half.sin();
+
+        Self{w: half.cos(), x: axis.x * s, y: axis.y * s, z: axis.z * s}
+    }
+
+    //if the rotation is zero the axis doesnt matter so it just picks x
+    pub fn to_axis_angle(self) -> (Point3D, f64)
+    {
+        let q = self.normalized();
+
+        let angle = 2.0 * q.w.clamp(-1.0, 1.0).acos();
+        let s = (1.0 - q.w * q.w).max(0.0).sqrt();
+
+        if s < 0.0001
+        {
+            (Point3D{x: 1.0, y: 0.0, z: 0.0}, angle)
+        } else
+        {
+            (Point3D{x: q.x, y: q.y, z: q.z} / s, angle)
+        }
+    }
+
+    pub fn dot(self, other: Self) -> f64
+    {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn normalized(self) -> Self
+    {
+        let magnitude = self.dot(self).sqrt();
+
+        Self{
+            w: self.w / magnitude,
+            x: self.x / magnitude,
+            y: self.y / magnitude,
+            z: self.z / magnitude
+        }
+    }
+
+    pub fn to_mat4x4(self) -> Mat4x4
+    {
+        let Self{w, x, y, z} = self.normalized();
+
+        Mat4x4{mat: [
+            [1.0-2.0*(y*y+z*z), 2.0*(x*y-w*z), 2.0*(x*z+w*y), 0.0],
+            [2.0*(x*y+w*z), 1.0-2.0*(x*x+z*z), 2.0*(y*z-w*x), 0.0],
+            [2.0*(x*z-w*y), 2.0*(y*z+w*x), 1.0-2.0*(x*x+y*y), 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ]}
+    }
+
+    pub fn slerp(self, other: Self, amount: f64) -> Self
+    {
+        let mut other = other;
+        let mut cos_angle = self.dot(other);
+
+        //q and -q are the same rotation, go the short way around
+        if cos_angle < 0.0
+        {
+            other = Self{w: -other.w, x: -other.x, y: -other.y, z: -other.z};
+            cos_angle = -cos_angle;
+        }
+
+        let (a, b) = if cos_angle > 0.9995
+        {
+            //almost the same orientation, plain lerp avoids dividing by a tiny sine
+            (1.0 - amount, amount)
+        } else
+        {
+            let angle = cos_angle.acos();
+            let sin_angle = angle.sin();
+
+            (((1.0 - amount) * angle).sin() / sin_angle, (amount * angle).sin() / sin_angle)
+        };
+
+        Self{
+            w: self.w * a + other.w * b,
+            x: self.x * a + other.x * b,
+            y: self.y * a + other.y * b,
+            z: self.z * a + other.z * b
+        }.normalized()
+    }
+}
+
+impl Mul for Quaternion
+{
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output
+    {
+        Self{
+            w: self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            x: self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            y: self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            z: self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn close_enough(a: f64, b: f64) -> bool
+    {
+        (a - b).abs() < 0.0001
+    }
+
+    //the rotation matrix transforms used to build straight from the axis and angle
+    fn axis_angle_matrix(axis: Point3D, angle: f64) -> Mat4x4
+    {
+        let Point3D{x, y, z} = axis.normalized();
+
+        let (sa, ca) = angle.sin_cos();
+        let nca = 1.0 - ca;
+
+        Mat4x4{mat: [
+            [ca+x*x*nca, x*y*nca-z*sa, x*z*nca+y*sa, 0.0],
+            [y*x*nca+z*sa, ca+y*y*nca, y*z*nca-x*sa, 0.0],
+            [z*x*nca-y*sa, z*y*nca+x*sa, ca+z*z*nca, 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ]}
+    }
+
+    #[test]
+    fn axis_angle_matches_matrix()
+    {
+        let axes = [
+            Point3D{x: 0.0, y: 1.0, z: 0.0},
+            Point3D{x: 1.0, y: 0.0, z: 0.0},
+            Point3D{x: 1.0, y: 2.0, z: -3.0}
+        ];
+
+        for axis in axes
+        {
+            for angle in [0.0, 0.5, 2.0, -1.3, 3.0]
+            {
+                let quaternion = Quaternion::from_axis_angle(axis, angle).to_mat4x4();
+                let matrix = axis_angle_matrix(axis, angle);
+
+                for (row, expected_row) in quaternion.mat.iter().zip(matrix.mat.iter())
+                {
+                    for (value, expected) in row.iter().zip(expected_row.iter())
+                    {
+                        assert!(close_enough(*value, *expected), "{value} {expected}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn slerp_endpoints()
+    {
+        let axis = Point3D{x: 0.0, y: 0.0, z: 1.0};
+
+        let a = Quaternion::from_axis_angle(axis, 0.2);
+        let b = Quaternion::from_axis_angle(axis, 1.8);
+
+        //q and -q are the same rotation
+        let same = |p: Quaternion, q: Quaternion|
+        {
+            let sign = p.dot(q).signum();
+
+            [(p.w, q.w), (p.x, q.x), (p.y, q.y), (p.z, q.z)].into_iter().all(|(a, b)| close_enough(a * sign, b))
+        };
+
+        assert!(same(a.slerp(b, 0.0), a));
+        assert!(same(a.slerp(b, 1.0), b));
+        assert!(same(a.slerp(b, 0.5), Quaternion::from_axis_angle(axis, 1.0)));
+
+        //nearly equal rotations take the lerp path and still land on the ends
+        let c = Quaternion::from_axis_angle(axis, 0.21);
+        assert!(same(a.slerp(c, 0.0), a));
+        assert!(same(a.slerp(c, 1.0), c));
+    }
+}
\ No newline at end of file
@@ -1,10 +1,37 @@
-use std::path::Path;
+use std::{
+    f64,
+    fs,
+    path::Path
+};
 
-use image::error::ImageError;
+use image::{
+    DynamicImage,
+    ImageFormat,
+    error::{ImageError, ParameterError, ParameterErrorKind}
+};
 
-use super::{Point2D, Color};
+use super::{Point2D, Point3D, Color, Gamma};
 
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSpace
+{
+    Linear,
+    Srgb
+}
+
+impl ColorSpace
+{
+    fn to_linear(self, value: f64) -> f64
+    {
+        match self
+        {
+            ColorSpace::Linear => value,
+            ColorSpace::Srgb => Gamma::Srgb.decode(value)
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Texture
 {
@@ -20,15 +47,51 @@ impl Texture
         Self{size, colors}
     }
 
-    pub fn load(filename: &Path) -> Result<Self, ImageError>
+    pub fn load(filename: &Path, color_space: ColorSpace) -> Result<Self, ImageError>
     {
-        let image = image::open(filename)?;
+        let bytes = fs::read(filename)?;
+
+        //some formats (like tga) have no magic bytes to guess from, the extension knows better
+        match ImageFormat::from_path(filename)
+        {
+            Ok(format) if image::guess_format(&bytes).is_err() =>
+            {
+                let image = image::load_from_memory_with_format(&bytes, format)?;
 
+                Self::from_image(image, color_space)
+            },
+            _ => Self::load_from_bytes(&bytes, color_space)
+        }
+    }
+
+    //the format gets guessed from the contents since theres no file name
+    pub fn load_from_bytes(bytes: &[u8], color_space: ColorSpace) -> Result<Self, ImageError>
+    {
+        Self::from_image(image::load_from_memory(bytes)?, color_space)
+    }
+
+    fn from_image(image: DynamicImage, color_space: ColorSpace) -> Result<Self, ImageError>
+    {
         let size = (image.width() as usize, image.height() as usize);
 
+        //theres no texel to sample in an empty image
+        if size.0 == 0 || size.1 == 0
+        {
+            return Err(ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::DimensionMismatch)));
+        }
+
+        //float images are already stored linearly
+        let color_space = match image
+        {
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => ColorSpace::Linear,
+            _ => color_space
+        };
+
         let colors = image.into_rgb32f().pixels().map(|pixel|
         {
-            Color::new(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64)
+            let convert = |value: f32| color_space.to_linear(value as f64);
+
+            Color::new(convert(pixel[0]), convert(pixel[1]), convert(pixel[2]))
         }).collect::<Vec<Color>>();
 
         Ok(Self{size, colors})
@@ -39,6 +102,19 @@ impl Texture
         self.pixel_local(self.to_local(position))
     }
 
+    //treats the texture as an equirectangular map around the viewer
+    pub fn sample_direction(&self, direction: Point3D) -> Color
+    {
+        let direction = direction.normalized();
+
+        let uv = Point2D{
+            x: 0.5 + direction.x.atan2(-direction.z) / f64::consts::TAU,
+            y: 0.5 + direction.y.clamp(-1.0, 1.0).asin() / f64::consts::PI
+        };
+
+        self.pixel(uv)
+    }
+
     fn pixel_local(&self, position: Point2D<usize>) -> Color
     {
         self.colors[((self.size.1 - position.y - 1) * self.size.0) + position.x]
@@ -46,9 +122,43 @@ impl Texture
 
     fn to_local(&self, position: Point2D) -> Point2D<usize>
     {
+        //a uv of exactly 1 lands one past the last texel
         Point2D{
-            x: (((position.x * self.size.0 as f64) as i32).max(0) as usize).min(self.size.0),
-            y: (((position.y * self.size.1 as f64) as i32).max(0) as usize).min(self.size.1)
+            x: (((position.x * self.size.0 as f64) as i32).max(0) as usize).min(self.size.0 - 1),
+            y: (((position.y * self.size.1 as f64) as i32).max(0) as usize).min(self.size.1 - 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn from_bytes()
+    {
+        let texture = Texture::load_from_bytes(b"P3 2 1 255 255 0 0 0 0 255", ColorSpace::Linear).unwrap();
+
+        assert_eq!(texture.size, (2, 1));
+
+        let left = texture.pixel(Point2D{x: 0.25, y: 0.5});
+        let right = texture.pixel(Point2D{x: 0.75, y: 0.5});
+
+        assert_eq!((left.r, left.b), (1.0, 0.0));
+        assert_eq!((right.r, right.b), (0.0, 1.0));
+
+        assert!(Texture::load_from_bytes(b"not an image", ColorSpace::Linear).is_err());
+    }
+
+    #[test]
+    fn empty_image()
+    {
+        for (width, height) in [(0, 0), (0, 3), (3, 0)]
+        {
+            let image = DynamicImage::new_rgb8(width, height);
+
+            assert!(Texture::from_image(image, ColorSpace::Linear).is_err());
         }
     }
 }
\ No newline at end of file
@@ -1,17 +1,101 @@
+use std::env;
+
 use crate::renderer::common::Color;
 use crate::renderer::normal_drawable::DrawableDisplay;
 
 
+pub const DEFAULT_CHARSET: &str =
+    "`.-':_,^=;><+!rc*/z?sLTv)J7(|Fi{C}fI31tlu[neoZ5Yxjya]2ESwqkP6h9d4VpOGbUAKXHm8RD#$Bg0MNWQ%&@";
+
+const BAYER: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5]
+];
+
+//what terminals actually show for each step of the color cube
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorMode
+{
+    Palette,
+    //nearest of the 256 colors by how different they look instead of per channel
+    Perceptual,
+    Truecolor
+}
+
 pub struct ConsoleScreen
 {
-    written: bool
+    written: bool,
+    color_mode: ColorMode,
+    charset: Vec<char>,
+    inverted: bool,
+    dither: bool,
+    palette: Vec<(u8, Color)>
 }
 
 impl ConsoleScreen
 {
-    pub fn new() -> Self
+    pub fn new(color_mode: ColorMode, charset: &str, inverted: bool) -> Self
+    {
+        let charset = charset.chars().collect();
+
+        let palette = if color_mode == ColorMode::Perceptual
+        {
+            Self::palette()
+        } else
+        {
+            Vec::new()
+        };
+
+        ConsoleScreen{written: false, color_mode, charset, inverted, dither: false, palette}
+    }
+
+    //the cube and the gray ramp, the first 16 colors depend on the terminal theme so theyre skipped
+    fn palette() -> Vec<(u8, Color)>
+    {
+        let level = |index: usize| CUBE_LEVELS[index] as f64 / 255.0;
+
+        let cube = (0..216).map(|index|
+        {
+            let color = Color::new(level(index / 36), level((index / 6) % 6), level(index % 6));
+
+            (16 + index as u8, color)
+        });
+
+        let grays = (0..24).map(|index|
+        {
+            let gray = (8 + index * 10) as f64 / 255.0;
+
+            (232 + index as u8, Color::new(gray, gray, gray))
+        });
+
+        cube.chain(grays).collect()
+    }
+
+    //redmean, eyes care the most about green and how much red vs blue matters depends on the red
+    fn perceptual_distance(a: Color, b: Color) -> f64
+    {
+        let red_mean = (a.r + b.r) / 2.0;
+
+        let (dr, dg, db) = (a.r - b.r, a.g - b.g, a.b - b.b);
+
+        (2.0 + red_mean) * dr * dr + 4.0 * dg * dg + (3.0 - red_mean) * db * db
+    }
+
+    pub fn set_dither(&mut self, dither: bool)
+    {
+        self.dither = dither;
+    }
+
+    pub fn supports_truecolor() -> bool
     {
-        ConsoleScreen{written: false}
+        env::var("COLORTERM").map(|value|
+        {
+            value == "truecolor" || value == "24bit"
+        }).unwrap_or(false)
     }
 
     fn move_cursor(&self, size: (usize, usize))
@@ -45,12 +129,9 @@ impl ConsoleScreen
         (winsize.ws_col as usize, winsize.ws_row as usize)
     }
 
-    fn output_color(color: Color)
+    fn output_color(&self, color: Color, position: (usize, usize))
     {
-        let charset =
-            "`.-':_,^=;><+!rc*/z?sLTv)J7(|Fi{C}fI31tlu[neoZ5Yxjya]2ESwqkP6h9d4VpOGbUAKXHm8RD#$Bg0MNWQ%&@";
-
-        let charset = charset.as_bytes();
+        let charset = &self.charset;
 
         let lightness = (color.r + color.g + color.b) / 3.0;
 
@@ -58,26 +139,61 @@ impl ConsoleScreen
 
         let index = light_index.min(charset.len()-1);
 
-        let character;
-
-        let inverted = false;
-        if inverted
+        let character = if self.inverted
         {
-            character = charset[charset.len() - 1 - index] as char;
+            charset[charset.len() - 1 - index]
         } else
         {
-            character = charset[index] as char;
-        }
+            charset[index]
+        };
 
-        let colorify = |color| ((color * 5.0) as u8).max(0).min(5);
+        match self.color_mode
+        {
+            ColorMode::Palette =>
+            {
+                //the threshold averages out to the real color over a few neighbouring pixels
+                let threshold = if self.dither
+                {
+                    (BAYER[position.1 % 4][position.0 % 4] as f64 + 0.5) / 16.0
+                } else
+                {
+                    0.0
+                };
+
+                let colorify = |color: f64| ((color * 5.0 + threshold) as u8).min(5);
+
+                let r = colorify(color.r);
+                let g = colorify(color.g);
+                let b = colorify(color.b);
+
+                let color_code = 16 + r * 36 + g * 6 + b;
+
+                print!("\x1b[38;5;{color_code}m{character}");
+            },
+            //the gray ramp is too fine for ordered dithering, it would just speckle the background
+            ColorMode::Perceptual =>
+            {
+                let color_code = self.palette.iter().min_by(|(_, a), (_, b)|
+                {
+                    let a = Self::perceptual_distance(color, *a);
+                    let b = Self::perceptual_distance(color, *b);
+
+                    a.total_cmp(&b)
+                }).map(|(code, _)| *code).unwrap_or(16);
 
-        let r = colorify(color.r);
-        let g = colorify(color.g);
-        let b = colorify(color.b);
+                print!("\x1b[38;5;{color_code}m{character}");
+            },
+            ColorMode::Truecolor =>
+            {
+                let colorify = |color: f64| (color.clamp(0.0, 1.0) * 255.0) as u8;
 
-        let color_code = 16 + r * 36 + g * 6 + b;
+                let r = colorify(color.r);
+                let g = colorify(color.g);
+                let b = colorify(color.b);
 
-        print!("\x1b[38;5;{color_code}m{character}");
+                print!("\x1b[38;2;{r};{g};{b}m{character}");
+            }
+        }
     }
 }
 
@@ -94,11 +210,11 @@ impl DrawableDisplay for ConsoleScreen
         self.written = true;
     }
 
-    fn display(&self, size: (usize, usize), colors: &[Color])
+    fn display(&mut self, size: (usize, usize), colors: &[Color], _: &[bool])
     {
         for (index, color) in colors.iter().enumerate()
         {
-            Self::output_color(*color);
+            self.output_color(*color, (index % size.0, index / size.0));
 
             if (index % size.0) == (size.0 - 1)
             {
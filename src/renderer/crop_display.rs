@@ -0,0 +1,53 @@
+use crate::renderer::common::{Color, Region};
+use crate::renderer::normal_drawable::DrawableDisplay;
+
+
+//only passes the pixels inside the region along, like one tile of a bigger image
+pub struct CropDisplay<D>
+{
+    display: D,
+    region: Region
+}
+
+impl<D> CropDisplay<D>
+{
+    pub fn new(display: D, region: Region) -> Self
+    {
+        Self{display, region}
+    }
+
+    fn clamped(&self, size: (usize, usize)) -> Region
+    {
+        Region{
+            x0: self.region.x0.min(size.0),
+            y0: self.region.y0.min(size.1),
+            x1: self.region.x1.min(size.0),
+            y1: self.region.y1.min(size.1)
+        }
+    }
+}
+
+impl<D: DrawableDisplay> DrawableDisplay for CropDisplay<D>
+{
+    fn prepare(&mut self, size: (usize, usize))
+    {
+        let size = self.clamped(size).size();
+
+        self.display.prepare(size);
+    }
+
+    fn display(&mut self, size: (usize, usize), colors: &[Color], coverage: &[bool])
+    {
+        let region = self.clamped(size);
+
+        let indices = (region.y0..region.y1).flat_map(|y|
+        {
+            (region.x0..region.x1).map(move |x| y * size.0 + x)
+        }).collect::<Vec<usize>>();
+
+        let colors = indices.iter().map(|index| colors[*index]).collect::<Vec<Color>>();
+        let coverage = indices.iter().map(|index| coverage[*index]).collect::<Vec<bool>>();
+
+        self.display.display(region.size(), &colors, &coverage);
+    }
+}
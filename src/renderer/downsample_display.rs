@@ -0,0 +1,119 @@
+use crate::renderer::common::Color;
+use crate::renderer::normal_drawable::DrawableDisplay;
+
+
+//averages blocks of a bigger render down to the output size, the colors are still linear
+//here so the gamma encode happens after averaging and edges dont come out darker
+pub struct DownsampleDisplay<D>
+{
+    display: D,
+    factor: usize
+}
+
+impl<D> DownsampleDisplay<D>
+{
+    pub fn new(display: D, factor: usize) -> Self
+    {
+        Self{display, factor: factor.max(1)}
+    }
+
+    fn output_size(&self, size: (usize, usize)) -> (usize, usize)
+    {
+        ((size.0 / self.factor).max(1), (size.1 / self.factor).max(1))
+    }
+}
+
+impl<D: DrawableDisplay> DrawableDisplay for DownsampleDisplay<D>
+{
+    fn prepare(&mut self, size: (usize, usize))
+    {
+        let size = self.output_size(size);
+
+        self.display.prepare(size);
+    }
+
+    fn display(&mut self, size: (usize, usize), colors: &[Color], coverage: &[bool])
+    {
+        let (width, height) = self.output_size(size);
+
+        let mut output_colors = Vec::with_capacity(width * height);
+        let mut output_coverage = Vec::with_capacity(width * height);
+
+        for y in 0..height
+        {
+            for x in 0..width
+            {
+                let mut color = Color::new(0.0, 0.0, 0.0);
+                let mut covered = 0;
+                let mut samples = 0;
+
+                for sample_y in (y * self.factor)..((y + 1) * self.factor).min(size.1)
+                {
+                    for sample_x in (x * self.factor)..((x + 1) * self.factor).min(size.0)
+                    {
+                        let index = sample_y * size.0 + sample_x;
+
+                        color = color + colors[index];
+                        if coverage[index]
+                        {
+                            covered += 1;
+                        }
+
+                        samples += 1;
+                    }
+                }
+
+                let samples = samples.max(1);
+
+                output_colors.push(color * (1.0 / samples as f64));
+
+                //the alpha is all or nothing so half covered pixels count as covered
+                output_coverage.push(covered * 2 >= samples);
+            }
+        }
+
+        self.display.display((width, height), &output_colors, &output_coverage);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use crate::renderer::common::Gamma;
+
+    struct Captured
+    {
+        colors: Vec<Color>
+    }
+
+    impl DrawableDisplay for Captured
+    {
+        fn prepare(&mut self, _: (usize, usize)) {}
+        fn display(&mut self, _: (usize, usize), colors: &[Color], _: &[bool])
+        {
+            self.colors = colors.to_vec();
+        }
+    }
+
+    #[test]
+    fn linear_edge()
+    {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let white = Color::new(1.0, 1.0, 1.0);
+
+        let mut display = DownsampleDisplay::new(Captured{colors: Vec::new()}, 2);
+        display.display((2, 2), &[black, white, black, white], &[true; 4]);
+
+        let colors = &display.display.colors;
+        assert_eq!(colors.len(), 1);
+
+        let color = colors[0];
+        assert!((color.r - 0.5).abs() < 0.0001);
+
+        //averaging already encoded values would give 127
+        let encoded = Gamma::Srgb.encode_u8(color.r);
+        assert!(encoded > 180, "{encoded}");
+    }
+}
@@ -0,0 +1,54 @@
+use image::{Rgb, Rgba, ImageBuffer, ImageFormat};
+
+use crate::renderer::common::Color;
+use crate::renderer::normal_drawable::DrawableDisplay;
+
+
+//keeps the colors linear and unclamped so exposure can be fixed up later
+pub struct ExrWriter
+{
+    filename: String,
+    transparent: bool
+}
+
+#[allow(dead_code)]
+impl ExrWriter
+{
+    pub fn new(filename: String, transparent: bool) -> Self
+    {
+        ExrWriter{filename, transparent}
+    }
+}
+
+#[allow(dead_code)]
+impl DrawableDisplay for ExrWriter
+{
+    fn prepare(&mut self, _: (usize, usize)) {}
+    fn display(&mut self, size: (usize, usize), colors: &[Color], coverage: &[bool])
+    {
+        if self.transparent
+        {
+            let mut image: ImageBuffer<Rgba<f32>, Vec<f32>> = ImageBuffer::new(size.0 as u32, size.1 as u32);
+
+            let pixels = image.pixels_mut().zip(colors.iter().zip(coverage.iter()));
+            for (pixel, (color, covered)) in pixels
+            {
+                let alpha = if *covered {1.0} else {0.0};
+
+                *pixel = Rgba([color.r as f32, color.g as f32, color.b as f32, alpha]);
+            }
+
+            image.save_with_format(&self.filename, ImageFormat::OpenExr).unwrap();
+        } else
+        {
+            let mut image: ImageBuffer<Rgb<f32>, Vec<f32>> = ImageBuffer::new(size.0 as u32, size.1 as u32);
+
+            for (pixel, color) in image.pixels_mut().zip(colors.iter())
+            {
+                *pixel = Rgb([color.r as f32, color.g as f32, color.b as f32]);
+            }
+
+            image.save_with_format(&self.filename, ImageFormat::OpenExr).unwrap();
+        }
+    }
+}
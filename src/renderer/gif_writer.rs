@@ -0,0 +1,62 @@
+use std::fs::File;
+
+use image::{
+    Rgba,
+    RgbaImage,
+    Frame,
+    Delay,
+    codecs::gif::{GifEncoder, Repeat}
+};
+
+use crate::renderer::common::{Color, Gamma};
+use crate::renderer::normal_drawable::DrawableDisplay;
+
+
+pub struct GifWriter
+{
+    encoder: GifEncoder<File>,
+    transparent: bool,
+    gamma: Gamma
+}
+
+#[allow(dead_code)]
+impl GifWriter
+{
+    pub fn new(filename: &str, transparent: bool, gamma: Gamma) -> Self
+    {
+        let mut encoder = GifEncoder::new(File::create(filename).unwrap());
+        encoder.set_repeat(Repeat::Infinite).unwrap();
+
+        GifWriter{encoder, transparent, gamma}
+    }
+
+    fn convert(&self, color: f64) -> u8
+    {
+        self.gamma.encode_u8(color)
+    }
+}
+
+impl DrawableDisplay for GifWriter
+{
+    fn prepare(&mut self, _: (usize, usize)) {}
+    fn display(&mut self, size: (usize, usize), colors: &[Color], coverage: &[bool])
+    {
+        let mut image = RgbaImage::new(size.0 as u32, size.1 as u32);
+
+        let pixels = image.pixels_mut().zip(colors.iter().zip(coverage.iter()));
+        for (pixel, (color, covered)) in pixels
+        {
+            let alpha = if *covered || !self.transparent {u8::MAX} else {0};
+
+            *pixel = Rgba([
+                self.convert(color.r),
+                self.convert(color.g),
+                self.convert(color.b),
+                alpha
+            ]);
+        }
+
+        let delay = Delay::from_numer_denom_ms(40, 1);
+        self.encoder.encode_frame(Frame::from_parts(image, 0, 0, delay)).unwrap();
+    }
+}
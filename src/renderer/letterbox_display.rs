@@ -0,0 +1,51 @@
+use crate::renderer::common::Color;
+use crate::renderer::normal_drawable::DrawableDisplay;
+
+
+//puts a smaller frame in the middle of the full size one, the bars around it stay uncovered
+pub struct LetterboxDisplay<D>
+{
+    display: D,
+    size: (usize, usize)
+}
+
+impl<D> LetterboxDisplay<D>
+{
+    pub fn new(display: D, size: (usize, usize)) -> Self
+    {
+        Self{display, size}
+    }
+}
+
+impl<D: DrawableDisplay> DrawableDisplay for LetterboxDisplay<D>
+{
+    fn prepare(&mut self, _size: (usize, usize))
+    {
+        self.display.prepare(self.size);
+    }
+
+    fn display(&mut self, size: (usize, usize), colors: &[Color], coverage: &[bool])
+    {
+        let (width, height) = self.size;
+
+        let offset_x = width.saturating_sub(size.0) / 2;
+        let offset_y = height.saturating_sub(size.1) / 2;
+
+        let mut output_colors = vec![Color::new(0.0, 0.0, 0.0); width * height];
+        let mut output_coverage = vec![false; width * height];
+
+        for y in 0..size.1.min(height)
+        {
+            for x in 0..size.0.min(width)
+            {
+                let index = y * size.0 + x;
+                let output_index = (y + offset_y) * width + x + offset_x;
+
+                output_colors[output_index] = colors[index];
+                output_coverage[output_index] = coverage[index];
+            }
+        }
+
+        self.display.display(self.size, &output_colors, &output_coverage);
+    }
+}
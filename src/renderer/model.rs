@@ -1,14 +1,26 @@
 use std::{
-    collections::{HashMap, hash_map::Entry},
-    path::Path,
+    collections::{HashMap, HashSet, hash_map::Entry},
+    path::{Path, PathBuf},
+    rc::Rc,
+    iter,
     io::{self, Read},
-    fs::File
+    fs::{self, File}
 };
 
 use image::error::ImageError;
 
-use crate::renderer::common::{Color, Point2D, Point3D, Texture};
+use zip::result::ZipError;
 
+use flate2::read::GzDecoder;
+
+use rayon::prelude::*;
+
+use crate::renderer::common::{Color, Point2D, Point3D, Texture, ColorSpace};
+
+use archive::Archive;
+
+mod ply;
+mod archive;
 
 #[allow(dead_code)]
 #[derive(Debug)]
@@ -26,12 +38,15 @@ enum ModelErrorType
     Material(Option<usize>),
     ParsingError(String),
     TextureLoadError(ImageError),
+    Archive(ZipError),
+    NoObjInArchive,
     GenericError,
     MissingValue,
     MissingMaterial,
     NoMaterial,
     NoNormals,
-    MalformedObj
+    MalformedObj,
+    MalformedPly
 }
 
 impl From<io::Error> for ModelError
@@ -91,17 +106,56 @@ impl<'a, I: Iterator<Item=&'a str>> ObjLine<'a, I>
 pub struct Material
 {
     pub diffuse_color: Option<Color>,
-    pub diffuse_texture: Option<Texture>
+    pub diffuse_texture: Option<Rc<Texture>>,
+    pub emissive: Option<Color>,
+    pub emissive_texture: Option<Rc<Texture>>,
+    pub shininess: Option<f64>,
+    pub shininess_texture: Option<Rc<Texture>>,
+    pub reflectivity: f64,
+    pub opacity: f64,
+    pub double_sided: bool
 }
 
 impl Material
 {
     pub fn new() -> Self
     {
-        Material{diffuse_color: None, diffuse_texture: None}
+        Material{
+            diffuse_color: None,
+            diffuse_texture: None,
+            emissive: None,
+            emissive_texture: None,
+            shininess: None,
+            shininess_texture: None,
+            reflectivity: 0.0,
+            opacity: 1.0,
+            double_sided: false
+        }
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+enum TextureSlot
+{
+    Diffuse,
+    Emissive,
+    Shininess
+}
+
+enum VertexData
+{
+    Position([f64; 3], Option<Color>),
+    Normal(Point3D),
+    Uv(Point2D)
+}
+
+struct PendingTexture
+{
+    material: Option<usize>,
+    slot: TextureSlot,
+    key: (PathBuf, ColorSpace)
+}
+
 struct Materials
 {
     fallback_material: Material,
@@ -117,7 +171,7 @@ impl Materials
         Materials{
             fallback_material: Material{
                 diffuse_color: Some(Color::new(0.5, 0.5, 0.5)),
-                diffuse_texture: None
+                ..Material::new()
             },
             material_indices: HashMap::new(),
             materials: Vec::new(),
@@ -165,16 +219,9 @@ impl Materials
         self.current_material
     }
 
-    pub fn set_current(&mut self, name: &str) -> Result<(), ModelErrorType>
+    pub fn index(&self, name: &str) -> Result<usize, ModelErrorType>
     {
-        let index = self.material_indices.get(name).ok_or(ModelErrorType::MissingMaterial);
-        self.current_material = match index
-        {
-            Ok(index) => Some(*index),
-            Err(_) => None
-        };
-
-        index.map(|_| ())
+        self.material_indices.get(name).copied().ok_or(ModelErrorType::MissingMaterial)
     }
 
     pub fn clear_current(&mut self)
@@ -188,10 +235,41 @@ impl Materials
         *material = Material{diffuse_color: Some(color), ..material.clone()};
     }
 
-    pub fn set_diffuse_texture(&mut self, texture: Texture)
+    //textures get loaded after the whole file is parsed so they dont go to the current material
+    pub fn set_texture(&mut self, index: Option<usize>, slot: TextureSlot, texture: Rc<Texture>)
     {
-        let material = self.current();
-        *material = Material{diffuse_texture: Some(texture), ..*material};
+        let material = match index
+        {
+            Some(index) => &mut self.materials[index],
+            None => &mut self.fallback_material
+        };
+
+        match slot
+        {
+            TextureSlot::Diffuse => material.diffuse_texture = Some(texture),
+            TextureSlot::Emissive => material.emissive_texture = Some(texture),
+            TextureSlot::Shininess => material.shininess_texture = Some(texture)
+        }
+    }
+
+    pub fn set_emissive(&mut self, color: Color)
+    {
+        self.current().emissive = Some(color);
+    }
+
+    pub fn set_shininess(&mut self, shininess: f64)
+    {
+        self.current().shininess = Some(shininess);
+    }
+
+    pub fn set_reflectivity(&mut self, reflectivity: f64)
+    {
+        self.current().reflectivity = reflectivity;
+    }
+
+    pub fn set_opacity(&mut self, opacity: f64)
+    {
+        self.current().opacity = opacity;
     }
 }
 
@@ -200,27 +278,185 @@ struct ModelParser<'a>
     parent: &'a mut Model,
     materials: Materials,
     normals: Vec<Point3D>,
-    uvs: Vec<Point2D>
+    uvs: Vec<Point2D>,
+    smoothing_group: Option<u32>,
+    //materials sharing a map share one decoded texture, failed loads are remembered too
+    textures: HashMap<(PathBuf, ColorSpace), Option<Rc<Texture>>>,
+    pending_textures: Vec<PendingTexture>,
+    //usemtl can name a material before its mtllib, so faces only remember the name until the end
+    material_names: Vec<String>,
+    current_name: Option<usize>,
+    face_materials: Vec<Option<usize>>,
+    //zipped models look up their materials and textures in here instead of the filesystem
+    archive: Option<Archive>
 }
 
 impl<'a> ModelParser<'a>
 {
     pub fn new(parent: &'a mut Model) -> Self
     {
-        ModelParser{parent, materials: Materials::new(), normals: Vec::new(), uvs: Vec::new()}
+        ModelParser{
+            parent,
+            materials: Materials::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            //files without any s statements get smoothed as one group
+            smoothing_group: Some(0),
+            textures: HashMap::new(),
+            pending_textures: Vec::new(),
+            material_names: Vec::new(),
+            current_name: None,
+            face_materials: Vec::new(),
+            archive: None
+        }
+    }
+
+    fn queue_texture(&mut self, slot: TextureSlot, path: PathBuf, color_space: ColorSpace)
+    {
+        self.pending_textures.push(PendingTexture{
+            material: self.materials.current_index(),
+            slot,
+            key: (path, color_space)
+        });
+    }
+
+    //decodes every new texture at once on all cores, then hands them out in the order they were asked for
+    fn load_pending_textures(&mut self)
+    {
+        let mut seen = HashSet::new();
+        let keys = self.pending_textures.iter()
+            .map(|pending| &pending.key)
+            .filter(|key| !self.textures.contains_key(*key) && seen.insert(*key))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let archive = &self.archive;
+        let decoded = keys.par_iter().map(|(path, color_space)|
+        {
+            match archive
+            {
+                Some(archive) => archive.read(path).map_err(ImageError::IoError).and_then(|bytes|
+                {
+                    Texture::load_from_bytes(bytes, *color_space)
+                }),
+                None => Texture::load(path, *color_space)
+            }
+        }).collect::<Vec<_>>();
+
+        for (key, texture) in keys.into_iter().zip(decoded)
+        {
+            let texture = match texture
+            {
+                Ok(texture) => Some(Rc::new(texture)),
+                Err(err) =>
+                {
+                    self.parent.warnings.push(format!("error loading texture {}: {err}", key.0.display()));
+
+                    None
+                }
+            };
+
+            self.textures.insert(key, texture);
+        }
+
+        for pending in self.pending_textures.drain(..)
+        {
+            if let Some(texture) = &self.textures[&pending.key]
+            {
+                self.materials.set_texture(pending.material, pending.slot, texture.clone());
+            }
+        }
     }
 
-    pub fn parse(&mut self, filename: &str) -> Result<(), ModelError>
+    pub fn parse(&mut self, filename: &str, progress: impl FnMut(f64)) -> Result<(), ModelError>
     {
-        let mut file_string = String::new();
-        File::open(filename)?.read_to_string(&mut file_string)?;
+        let is_zip = Path::new(filename).extension()
+            .map(|extension| extension.eq_ignore_ascii_case("zip"))
+            .unwrap_or(false);
+
+        if is_zip
+        {
+            return self.parse_zip(filename, progress);
+        }
+
+        let mut data = Vec::new();
+        File::open(filename)?.read_to_end(&mut data)?;
+
+        //gzip files start with these magic bytes, no matter what theyre named
+        let file_string = if data.starts_with(&[0x1f, 0x8b])
+        {
+            let mut file_string = String::new();
+            GzDecoder::new(data.as_slice()).read_to_string(&mut file_string)?;
+
+            file_string
+        } else
+        {
+            String::from_utf8(data).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?
+        };
 
         let parent_dir = Path::new(filename).parent().unwrap_or_else(|| Path::new(""));
 
-        for line in Self::parse_obj(&file_string)
+        self.parse_str(&file_string, Some(parent_dir), progress)
+    }
+
+    //the obj closest to the root of the archive gets loaded, everything it references comes from the archive too
+    fn parse_zip(&mut self, filename: &str, progress: impl FnMut(f64)) -> Result<(), ModelError>
+    {
+        let model_error = |error_type| ModelError{line_index: None, error_type};
+
+        let archive = Archive::open(Path::new(filename)).map_err(model_error)?;
+
+        let obj_path = archive.obj_path().ok_or(model_error(ModelErrorType::NoObjInArchive))?.to_owned();
+
+        let file_string = String::from_utf8(archive.read(&obj_path)?.to_vec())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        self.archive = Some(archive);
+
+        let parent_dir = obj_path.parent().unwrap_or_else(|| Path::new(""));
+
+        self.parse_str(&file_string, Some(parent_dir), progress)
+    }
+
+    //without a parent directory there is nowhere to look for materials and textures
+    pub fn parse_str(
+        &mut self,
+        text: &str,
+        parent_dir: Option<&Path>,
+        mut progress: impl FnMut(f64)
+    ) -> Result<(), ModelError>
+    {
+        let total_lines = text.lines().count().max(1);
+        let mut last_percent = 0;
+
+        let lines = Self::parse_obj(text).collect::<Vec<_>>();
+
+        //vertex data doesnt depend on anything before it, so its all parsed up front on every core
+        //and only gets added (or fails) once the serial pass below reaches its line
+        let vertex_data = lines.par_iter().map(|line|
+        {
+            matches!(line.field, "v" | "vn" | "vt").then(|| Self::parse_vertex_data(line.clone()))
+        }).collect::<Vec<_>>();
+
+        for (line, vertex_data) in lines.into_iter().zip(vertex_data)
         {
             let index = line.index;
-            if let Err(error_type) = self.parse_obj_line(parent_dir, line)
+
+            //only report whole percents so the callback doesnt slow down parsing
+            let percent = index * 100 / total_lines;
+            if percent != last_percent
+            {
+                last_percent = percent;
+                progress(index as f64 / total_lines as f64);
+            }
+
+            let result = match vertex_data
+            {
+                Some(data) => data.map(|data| self.push_vertex_data(data)),
+                None => self.parse_obj_line(parent_dir, line)
+            };
+
+            if let Err(error_type) = result
             {
                 match error_type
                 {
@@ -237,12 +473,37 @@ impl<'a> ModelParser<'a>
             }
         }
 
+        self.resolve_materials();
+
+        progress(1.0);
+
         Ok(())
     }
 
+    fn resolve_materials(&mut self)
+    {
+        //every mtllib has been read by now so all the textures decode in one go
+        self.load_pending_textures();
+
+        let resolved = self.material_names.iter().map(|name|
+        {
+            self.materials.index(name).map_err(|_|
+            {
+                eprintln!("material {name} isnt defined, using fallback");
+            }).ok()
+        }).collect::<Vec<Option<usize>>>();
+
+        self.parent.material_indices.extend(self.face_materials.drain(..).map(|name|
+        {
+            name.and_then(|name| resolved[name])
+        }));
+
+        self.parent.materials = self.materials.materials().to_vec();
+    }
+
     fn parse_obj_line<'b, I: Iterator<Item=&'b str>>(
         &mut self,
-        parent_dir: &Path,
+        parent_dir: Option<&Path>,
         line: ObjLine<'b, I>
     ) -> Result<(), ModelErrorType>
     {
@@ -250,62 +511,85 @@ impl<'a> ModelParser<'a>
         {
             "mtllib" =>
             {
-                let path = parent_dir.join(Self::correctify_path(line.rest()));
+                let parent_dir = if let Some(parent_dir) = parent_dir
+                {
+                    parent_dir
+                } else
+                {
+                    eprintln!("ignoring mtllib {}, no directory to load it from", line.rest().trim());
+                    return Ok(());
+                };
+
+                let path = self.resolve_path(line.rest(), &[parent_dir]);
 
-                let mut mtl_string = String::new();
-                File::open(path)?.read_to_string(&mut mtl_string)?;
+                let mtl_string = self.read_to_string(&path)?;
+
+                let mtl_dir = path.parent().unwrap_or(parent_dir);
+                let directories = [parent_dir, mtl_dir];
 
                 for line in Self::parse_obj(&mtl_string)
                 {
                     let index = line.index;
-                    if let Err(_) = self.parse_mtl_line(parent_dir, line)
+                    if self.parse_mtl_line(&directories, line).is_err()
                     {
                         return Err(ModelErrorType::Material(Some(index)));
                     }
                 }
 
                 self.materials.clear_current();
-                self.parent.materials.extend(self.materials.materials().iter().cloned());
 
                 Ok(())
             },
             "usemtl" =>
             {
-                let _ = self.materials.set_current(&line.rest().to_owned());
+                let name = line.rest().trim();
 
-                Ok(())
-            },
-            "v" =>
-            {
-                let mut vertices = Self::parse_floats(line.values, 3)?;
-                self.parent.vertices.append(&mut vertices);
+                self.current_name = match self.material_names.iter().position(|known| known == name)
+                {
+                    Some(index) => Some(index),
+                    None =>
+                    {
+                        self.material_names.push(name.to_owned());
+
+                        Some(self.material_names.len() - 1)
+                    }
+                };
 
                 Ok(())
             },
-            "vn" =>
+            "v" | "vn" | "vt" =>
             {
-                let normal = Self::parse_floats(line.values, 3)?;
-
-                self.normals.push(Point3D{x: normal[0], y: normal[1], z: normal[2]}.normalized());
+                let data = Self::parse_vertex_data(line)?;
+                self.push_vertex_data(data);
 
                 Ok(())
             },
-            "vt" =>
+            "s" =>
             {
-                let uv = Self::parse_floats(line.values, 2)?;
+                let group = line.rest().trim();
+
+                self.smoothing_group = if group == "off"
+                {
+                    None
+                } else
+                {
+                    let group: u32 = group.parse()
+                        .map_err(|_| ModelErrorType::ParsingError(group.to_owned()))?;
 
-                self.uvs.push(Point2D{x: uv[0], y: uv[1]});
+                    (group != 0).then_some(group)
+                };
 
                 Ok(())
             },
             "f" => self.parse_face(line.values),
+            "l" => self.parse_line(line.values),
             _ => Ok(())
         }
     }
 
     fn parse_mtl_line<'b, I: Iterator<Item=&'b str>>(
         &mut self,
-        parent_dir: &Path,
+        directories: &[&Path],
         mut line: ObjLine<'b, I>
     ) -> Result<(), ModelErrorType>
     {
@@ -335,13 +619,81 @@ impl<'a> ModelParser<'a>
             },
             "map_Kd" =>
             {
-                let path = parent_dir.join(Self::correctify_path(line.rest()));
+                let path = self.resolve_path(line.rest(), directories);
+
+                self.queue_texture(TextureSlot::Diffuse, path, ColorSpace::Srgb);
 
-                match Texture::load(&path)
+                Ok(())
+            },
+            "Ke" =>
+            {
+                let mut component = || -> Result<f64, ModelErrorType>
                 {
-                    Ok(texture) => self.materials.set_diffuse_texture(texture),
-                    Err(err) => eprintln!("error loading texture {err}")
-                }
+                    line.next_value()?.trim().parse().map_err(|_| ModelErrorType::Material(None))
+                };
+
+                let color = Color::new(
+                    component()?,
+                    component()?,
+                    component()?
+                );
+
+                self.materials.set_emissive(color);
+
+                Ok(())
+            },
+            "map_Ke" =>
+            {
+                let path = self.resolve_path(line.rest(), directories);
+
+                self.queue_texture(TextureSlot::Emissive, path, ColorSpace::Srgb);
+
+                Ok(())
+            },
+            "Ns" =>
+            {
+                let value = line.next_value()?.trim();
+                let shininess: f64 = value.parse().map_err(|_| ModelErrorType::Material(None))?;
+
+                //below 1 the highlight would spread over the whole lit side
+                self.materials.set_shininess(shininess.max(1.0));
+
+                Ok(())
+            },
+            //gloss maps are data, not colors
+            "map_Ns" =>
+            {
+                let path = self.resolve_path(line.rest(), directories);
+
+                self.queue_texture(TextureSlot::Shininess, path, ColorSpace::Linear);
+
+                Ok(())
+            },
+            //the pbr extension calls it metallic but its close enough
+            "Pm" =>
+            {
+                let value = line.next_value()?.trim();
+                let reflectivity: f64 = value.parse().map_err(|_| ModelErrorType::Material(None))?;
+
+                self.materials.set_reflectivity(reflectivity.clamp(0.0, 1.0));
+
+                Ok(())
+            },
+            //some exporters write the transparency instead of the dissolve
+            "d" | "Tr" =>
+            {
+                let value = line.next_value()?.trim();
+                let value: f64 = value.parse().map_err(|_| ModelErrorType::Material(None))?;
+
+                let opacity = if line.field == "Tr"
+                {
+                    1.0 - value
+                } else
+                {
+                    value
+                };
+
+                self.materials.set_opacity(opacity.clamp(0.0, 1.0));
 
                 Ok(())
             },
@@ -356,13 +708,68 @@ impl<'a> ModelParser<'a>
         wrong_path.trim().replace('\\', "/")
     }
 
-    fn parse_obj<'b>(text: &'b str) -> impl Iterator<Item=ObjLine<'b, impl Iterator<Item=&'b str>>>
+    //exporters write paths relative to whatever they felt like (or absolute ones from their machine),
+    //so try it as written, then next to each file, then just the file name next to each file
+    fn resolve_path(&self, written: &str, directories: &[&Path]) -> PathBuf
+    {
+        let path = PathBuf::from(Self::correctify_path(written));
+        let file_name = path.file_name().map(PathBuf::from);
+
+        let mut candidates = iter::once(path.clone())
+            .chain(directories.iter().map(|directory| directory.join(&path)))
+            .chain(file_name.iter().flat_map(|file_name|
+            {
+                directories.iter().map(move |directory| directory.join(file_name))
+            }));
+
+        let exists = |candidate: &PathBuf|
+        {
+            match &self.archive
+            {
+                Some(archive) => archive.contains(candidate),
+                None => candidate.is_file()
+            }
+        };
+
+        //where the path points when taken as written
+        let expected = directories.first().map(|directory| directory.join(&path)).unwrap_or(path.clone());
+
+        match candidates.find(exists)
+        {
+            Some(found) =>
+            {
+                if found != path && found != expected
+                {
+                    eprintln!("using {} for {}", found.display(), written.trim());
+                }
+
+                found
+            },
+            //nothing exists so the error comes from where it was supposed to be
+            None => expected
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String>
+    {
+        match &self.archive
+        {
+            Some(archive) =>
+            {
+                String::from_utf8(archive.read(path)?.to_vec())
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+            },
+            None => fs::read_to_string(path)
+        }
+    }
+
+    fn parse_obj<'b>(text: &'b str) -> impl Iterator<Item=ObjLine<'b, impl Iterator<Item=&'b str> + Clone>>
     {
         text.lines().enumerate().filter_map(|(index, line)|
         {
             let line = Self::remove_comments(line).trim();
 
-            if line.len() == 0
+            if line.is_empty()
             {
                 return None;
             }
@@ -382,6 +789,71 @@ impl<'a> ModelParser<'a>
         })
     }
 
+    //doesnt touch the parser so these lines can be parsed on any thread in any order
+    fn parse_vertex_data<'b, I: Iterator<Item=&'b str>>(line: ObjLine<'b, I>) -> Result<VertexData, ModelErrorType>
+    {
+        match line.field
+        {
+            "v" =>
+            {
+                let values = line.values.filter(|value| !value.is_empty()).collect::<Vec<&str>>();
+
+                let position = Self::parse_floats(values.iter().copied(), 3)?;
+
+                //some exporters put the vertex color right after the position
+                let color = if values.len() >= 6
+                {
+                    let color = Self::parse_floats(values[3..].iter().copied(), 3)?;
+
+                    Some(Color::new(color[0], color[1], color[2]))
+                } else
+                {
+                    None
+                };
+
+                Ok(VertexData::Position([position[0], position[1], position[2]], color))
+            },
+            "vn" =>
+            {
+                let normal = Self::parse_floats(line.values, 3)?;
+
+                Ok(VertexData::Normal(Point3D{x: normal[0], y: normal[1], z: normal[2]}.normalized()))
+            },
+            "vt" =>
+            {
+                let uv = line.values.filter(|value| !value.is_empty())
+                    .map(Self::parse_float)
+                    .collect::<Result<Vec<f64>, _>>()?;
+
+                //1d textures leave out v, 3d ones add a w that nothing here uses
+                let uv = match uv.as_slice()
+                {
+                    [u] => Point2D{x: *u, y: 0.0},
+                    [u, v] | [u, v, _] => Point2D{x: *u, y: *v},
+                    [] => return Err(ModelErrorType::MissingValue),
+                    _ => return Err(ModelErrorType::MalformedObj)
+                };
+
+                Ok(VertexData::Uv(uv))
+            },
+            field => Err(ModelErrorType::ParsingError(field.to_owned()))
+        }
+    }
+
+    fn push_vertex_data(&mut self, data: VertexData)
+    {
+        match data
+        {
+            VertexData::Position(position, color) =>
+            {
+                self.parent.vertices.extend(position);
+                self.parent.vertex_colors.push(color);
+            },
+            VertexData::Normal(normal) => self.normals.push(normal),
+            VertexData::Uv(uv) => self.uvs.push(uv)
+        }
+    }
+
     fn parse_floats<'b>(
         mut unparsed: impl Iterator<Item=&'b str>,
         amount: usize
@@ -401,11 +873,18 @@ impl<'a> ModelParser<'a>
                 break value;
             };
 
-            value.trim().parse()
-                .map_err(|_| ModelErrorType::ParsingError(value.to_owned()))
+            Self::parse_float(value)
         }).collect()
     }
 
+    //rust happily parses nan and inf, which then quietly wreck every transform
+    fn parse_float(value: &str) -> Result<f64, ModelErrorType>
+    {
+        value.trim().parse::<f64>().ok()
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| ModelErrorType::ParsingError(value.to_owned()))
+    }
+
     fn parse_face<'b>(
         &mut self,
         unparsed: impl Iterator<Item=&'b str>
@@ -431,51 +910,62 @@ impl<'a> ModelParser<'a>
 
             for (index_type, index) in value.split('/').enumerate()
             {
-                let value: i32 = index.trim().parse()
+                //v//vn leaves the texture index empty
+                if index.trim().is_empty() && index_type != 0
+                {
+                    continue;
+                }
+
+                let value: i64 = index.trim().parse()
                     .map_err(|_| ModelErrorType::GenericError)?;
 
+                let available = match index_type
+                {
+                    0 => self.parent.vertices.len() / 3,
+                    1 => self.uvs.len(),
+                    2 => self.normals.len(),
+                    _ => continue
+                };
+
                 let value = if value < 0
                 {
                     //start from the back
-                    (self.parent.vertices.len() as i32 + value) as usize
+                    available as i64 + value
                 } else
                 {
                     //the indices start from 1 in it for some reason??
-                    (value - 1) as usize
+                    value - 1
                 };
 
-                match index_type
+                //a face pointing past whats been defined so far means the file is broken
+                if value < 0 || value >= available as i64
                 {
-                    0 =>
-                    {
-                        face_point = Some(FacePoint{position: value, texture: None, normal: None});
-                    },
-                    1 =>
-                    {
-                        face_point = Some(FacePoint{
-                            texture: Some(value),
-                            ..face_point.expect("already initialized")
-                        });
-                    },
-                    2 =>
-                    {
-                        face_point = Some(FacePoint{
-                            normal: Some(value),
-                            ..face_point.expect("already initialized")
-                        });
-                    },
-                    _ => ()
+                    return Err(ModelErrorType::MalformedObj);
                 }
-            }
 
-            face.push(face_point.expect("all faces have at least vertices"));
-        }
+                let value = value as usize;
+
+                face_point = match (index_type, face_point)
+                {
+                    (0, _) => Some(FacePoint{position: value, texture: None, normal: None}),
+                    (1, Some(face_point)) => Some(FacePoint{texture: Some(value), ..face_point}),
+                    (_, Some(face_point)) => Some(FacePoint{normal: Some(value), ..face_point}),
+                    (_, None) => return Err(ModelErrorType::MalformedObj)
+                };
+            }
+
+            face.push(face_point.ok_or(ModelErrorType::MalformedObj)?);
+        }
 
         if face.len() < 3
         {
             return Err(ModelErrorType::GenericError);
         }
 
+        let triangles = Self::triangulate(
+            &face.iter().map(|point| self.parent.vertex(point.position)).collect::<Vec<Point3D>>()
+        );
+
         let mut insert_face = |index| -> Result<(), ModelErrorType>
         {
             let face: &FacePoint = &face[index];
@@ -495,19 +985,149 @@ impl<'a> ModelParser<'a>
             Ok(())
         };
 
-        for v in 2..face.len()
+        for [a, b, c] in triangles
         {
-            insert_face(v - 1)?;
-            insert_face(v)?;
-            insert_face(0)?;
+            insert_face(a)?;
+            insert_face(b)?;
+            insert_face(c)?;
+
+            self.face_materials.push(self.current_name);
+            self.parent.smoothing_groups.push(self.smoothing_group);
+        }
+
+        Ok(())
+    }
 
-            self.parent.material_indices.push(self.materials.current_index());
+    //only the positions matter, texture indices after a slash get skipped
+    fn parse_line<'b>(
+        &mut self,
+        unparsed: impl Iterator<Item=&'b str>
+    ) -> Result<(), ModelErrorType>
+    {
+        let available = self.parent.vertices.len() / 3;
+
+        let points = unparsed.filter(|value| !value.is_empty()).map(|value|
+        {
+            let index = value.split('/').next().unwrap_or(value);
+            let index: i64 = index.trim().parse().map_err(|_| ModelErrorType::GenericError)?;
+
+            let index = if index < 0
+            {
+                available as i64 + index
+            } else
+            {
+                index - 1
+            };
+
+            if index < 0 || index >= available as i64
+            {
+                return Err(ModelErrorType::MalformedObj);
+            }
+
+            Ok(index as usize)
+        }).collect::<Result<Vec<usize>, _>>()?;
+
+        if points.len() < 2
+        {
+            return Err(ModelErrorType::MissingValue);
+        }
+
+        for segment in points.windows(2)
+        {
+            self.parent.line_indices.extend(segment);
         }
 
         Ok(())
     }
 
-    fn remove_comments<'b>(line: &'b str) -> &'b str
+    //convex faces get fanned around the first point, concave ones get their ears clipped off
+    fn triangulate(points: &[Point3D]) -> Vec<[usize; 3]>
+    {
+        let fan = |indices: &[usize]|
+        {
+            (2..indices.len()).map(|v| [indices[v - 1], indices[v], indices[0]]).collect::<Vec<_>>()
+        };
+
+        let all = (0..points.len()).collect::<Vec<usize>>();
+
+        if points.len() == 3
+        {
+            return fan(&all);
+        }
+
+        //the winding of the whole polygon decides which corners point inwards
+        let normal = (0..points.len()).fold(Point3D{x: 0.0, y: 0.0, z: 0.0}, |normal, index|
+        {
+            normal + points[index].cross(points[(index + 1) % points.len()])
+        });
+
+        let turn = |a: usize, b: usize, c: usize|
+        {
+            (points[b] - points[a]).cross(points[c] - points[b]).dot(normal)
+        };
+
+        let convex = (0..points.len()).all(|index|
+        {
+            let previous = (index + points.len() - 1) % points.len();
+
+            turn(previous, index, (index + 1) % points.len()) >= 0.0
+        });
+
+        if convex
+        {
+            return fan(&all);
+        }
+
+        let inside = |point: usize, [a, b, c]: [usize; 3]|
+        {
+            [(a, b), (b, c), (c, a)].into_iter().all(|(start, end)|
+            {
+                (points[end] - points[start]).cross(points[point] - points[start]).dot(normal) >= 0.0
+            })
+        };
+
+        let mut remaining = all;
+        let mut triangles = Vec::new();
+
+        while remaining.len() > 3
+        {
+            let count = remaining.len();
+
+            let ear = (0..count).find(|index|
+            {
+                let triangle = [
+                    remaining[(index + count - 1) % count],
+                    remaining[*index],
+                    remaining[(index + 1) % count]
+                ];
+
+                turn(triangle[0], triangle[1], triangle[2]) > 0.0
+                    && !remaining.iter().any(|point| !triangle.contains(point) && inside(*point, triangle))
+            });
+
+            match ear
+            {
+                Some(index) =>
+                {
+                    triangles.push([
+                        remaining[(index + count - 1) % count],
+                        remaining[index],
+                        remaining[(index + 1) % count]
+                    ]);
+
+                    remaining.remove(index);
+                },
+                //self intersecting or degenerate, theres no right answer so just fan the rest
+                None => break
+            }
+        }
+
+        triangles.extend(fan(&remaining));
+
+        triangles
+    }
+
+    fn remove_comments(line: &str) -> &str
     {
         let comment_pos = line.find('#');
 
@@ -524,11 +1144,20 @@ impl<'a> ModelParser<'a>
 pub struct Model
 {
     pub vertices: Vec<f64>,
+    pub vertex_colors: Vec<Option<Color>>,
     pub indices: Vec<usize>,
+    //pairs of vertices with a line between them
+    pub line_indices: Vec<usize>,
     pub material_indices: Vec<Option<usize>>,
+    pub smoothing_groups: Vec<Option<u32>>,
     pub normals: Vec<Point3D>,
     pub uvs: Vec<Point2D>,
-    pub materials: Vec<Material>
+    //empty until generate_tangents gets called
+    pub tangents: Vec<Point3D>,
+    pub materials: Vec<Material>,
+    //textures that couldnt be loaded, their materials fall back to the plain colors
+    pub warnings: Vec<String>,
+    generated_normals: bool
 }
 
 #[allow(dead_code)]
@@ -538,21 +1167,988 @@ impl Model
     {
         Model{
             vertices: Vec::new(),
+            vertex_colors: Vec::new(),
             indices: Vec::new(),
+            line_indices: Vec::new(),
             material_indices: Vec::new(),
+            smoothing_groups: Vec::new(),
             normals: Vec::new(),
             uvs: Vec::new(),
-            materials: Vec::new()
+            tangents: Vec::new(),
+            materials: Vec::new(),
+            warnings: Vec::new(),
+            generated_normals: false
+        }
+    }
+
+    //a flat grid facing up, split into tiles so the parts behind the camera can get culled
+    pub fn floor(height: f64, extent: f64, tiles: usize) -> Self
+    {
+        let mut model = Model::new();
+
+        let tile_size = extent * 2.0 / tiles as f64;
+
+        for z in 0..=tiles
+        {
+            for x in 0..=tiles
+            {
+                model.vertices.extend([
+                    x as f64 * tile_size - extent,
+                    height,
+                    z as f64 * tile_size - extent
+                ]);
+            }
+        }
+
+        let index = |x: usize, z: usize| z * (tiles + 1) + x;
+
+        for z in 0..tiles
+        {
+            for x in 0..tiles
+            {
+                //counter clockwise when looking down from above
+                model.indices.extend([
+                    index(x, z), index(x, z + 1), index(x + 1, z),
+                    index(x + 1, z), index(x, z + 1), index(x + 1, z + 1)
+                ]);
+
+                model.uvs.extend([
+                    Point2D{x: 0.0, y: 1.0}, Point2D{x: 0.0, y: 0.0}, Point2D{x: 1.0, y: 1.0},
+                    Point2D{x: 1.0, y: 1.0}, Point2D{x: 0.0, y: 0.0}, Point2D{x: 1.0, y: 0.0}
+                ]);
+
+                model.material_indices.extend([None, None]);
+            }
+        }
+
+        model.normals = vec![Point3D{x: 0.0, y: 1.0, z: 0.0}; model.indices.len()];
+
+        model
+    }
+
+    pub fn read(filename: &str) -> Result<Self, ModelError>
+    {
+        Self::read_with_progress(filename, |_| {})
+    }
+
+    //ply files load in one go so they dont report any progress
+    pub fn read_with_progress(filename: &str, progress: impl FnMut(f64)) -> Result<Self, ModelError>
+    {
+        let is_ply = Path::new(filename).extension()
+            .map(|extension| extension.eq_ignore_ascii_case("ply"))
+            .unwrap_or(false);
+
+        if is_ply
+        {
+            Self::read_ply(filename)
+        } else
+        {
+            Self::read_obj_with_progress(filename, progress)
         }
     }
 
     pub fn read_obj(filename: &str) -> Result<Self, ModelError>
+    {
+        Self::read_obj_with_progress(filename, |_| {})
+    }
+
+    //progress gets called with the fraction of lines parsed so far
+    pub fn read_obj_with_progress(filename: &str, progress: impl FnMut(f64)) -> Result<Self, ModelError>
     {
         let mut model = Model::new();
         let mut parser = ModelParser::new(&mut model);
 
-        parser.parse(filename)?;
+        parser.parse(filename, progress)?;
+
+        model.validate()?;
+
+        if model.normals.is_empty()
+        {
+            model.generate_normals();
+        }
 
         Ok(model)
     }
-}
\ No newline at end of file
+
+    //mtllib lines are skipped since theres no directory to find them in
+    pub fn read_obj_from_reader(mut reader: impl Read) -> Result<Self, ModelError>
+    {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+
+        Self::read_obj_str(&text)
+    }
+
+    pub fn read_obj_str(text: &str) -> Result<Self, ModelError>
+    {
+        let mut model = Model::new();
+        let mut parser = ModelParser::new(&mut model);
+
+        parser.parse_str(text, None, |_| {})?;
+
+        model.validate()?;
+
+        if model.normals.is_empty()
+        {
+            model.generate_normals();
+        }
+
+        Ok(model)
+    }
+
+    //catches whatever would make drawing index out of bounds, like only some faces having normals
+    fn validate(&self) -> Result<(), ModelError>
+    {
+        let vertices = self.vertices.len() / 3;
+
+        let per_corner = |amount: usize| amount == 0 || amount == self.indices.len();
+
+        let valid = self.indices.len() % 3 == 0
+            && self.line_indices.len() % 2 == 0
+            && self.indices.iter().chain(self.line_indices.iter()).all(|index| *index < vertices)
+            && per_corner(self.normals.len())
+            && per_corner(self.uvs.len());
+
+        if valid
+        {
+            Ok(())
+        } else
+        {
+            Err(ModelError{line_index: None, error_type: ModelErrorType::MalformedObj})
+        }
+    }
+
+    pub fn read_ply(filename: &str) -> Result<Self, ModelError>
+    {
+        let mut model = ply::read_ply(filename)?;
+
+        if model.normals.is_empty()
+        {
+            model.generate_normals();
+        }
+
+        Ok(model)
+    }
+
+    pub fn vertex(&self, index: usize) -> Point3D
+    {
+        Point3D{
+            x: self.vertices[index * 3],
+            y: self.vertices[index * 3 + 1],
+            z: self.vertices[index * 3 + 2]
+        }
+    }
+
+    //min and max corners, an empty model is just a point at the origin
+    pub fn bounding_box(&self) -> (Point3D, Point3D)
+    {
+        let zero = Point3D{x: 0.0, y: 0.0, z: 0.0};
+
+        (0..(self.vertices.len() / 3)).map(|index| self.vertex(index)).fold(None, |bounds, point|
+        {
+            let (low, high): (Point3D, Point3D) = bounds.unwrap_or((point, point));
+
+            Some((
+                Point3D{x: low.x.min(point.x), y: low.y.min(point.y), z: low.z.min(point.z)},
+                Point3D{x: high.x.max(point.x), y: high.y.max(point.y), z: high.z.max(point.z)}
+            ))
+        }).unwrap_or((zero, zero))
+    }
+
+    //moves the bounding box center to the origin so rotations pivot around the middle
+    pub fn center(&mut self)
+    {
+        let (low, high) = self.bounding_box();
+        let center = (low + high) / 2.0;
+
+        for position in self.vertices.chunks_exact_mut(3)
+        {
+            position[0] -= center.x;
+            position[1] -= center.y;
+            position[2] -= center.z;
+        }
+    }
+
+    //uniformly scales so the longest side of the bounding box is 1
+    pub fn normalize_scale(&mut self)
+    {
+        let (low, high) = self.bounding_box();
+        let size = high - low;
+
+        let longest = size.x.max(size.y).max(size.z);
+        if longest <= 0.0
+        {
+            return;
+        }
+
+        for value in self.vertices.iter_mut()
+        {
+            *value /= longest;
+        }
+    }
+
+    fn smoothing_group(&self, triangle: usize) -> Option<u32>
+    {
+        //models without smoothing info are smoothed as a whole
+        self.smoothing_groups.get(triangle).copied().unwrap_or(Some(0))
+    }
+
+    fn face_normal(&self, triangle: usize) -> Point3D
+    {
+        let p0 = self.vertex(self.indices[triangle * 3]);
+        let p1 = self.vertex(self.indices[triangle * 3 + 1]);
+        let p2 = self.vertex(self.indices[triangle * 3 + 2]);
+
+        //the cross product is already scaled by the area of the triangle
+        (p1 - p0).cross(p2 - p0)
+    }
+
+    //turns every triangle around, for models made with clockwise front faces
+    pub fn flip_winding(&mut self)
+    {
+        fn flip<T>(values: &mut [T])
+        {
+            for triangle in values.chunks_exact_mut(3)
+            {
+                triangle.swap(1, 2);
+            }
+        }
+
+        flip(&mut self.indices);
+        flip(&mut self.uvs);
+        flip(&mut self.tangents);
+
+        if self.generated_normals
+        {
+            self.generate_normals();
+        } else
+        {
+            flip(&mut self.normals);
+        }
+    }
+
+    //with normals from the file most faces should agree with them, otherwise a closed
+    //mesh has a negative volume when its faces go the wrong way around
+    pub fn clockwise(&self) -> bool
+    {
+        let triangles = self.indices.len() / 3;
+
+        if !self.generated_normals && self.normals.len() == self.indices.len()
+        {
+            let disagreeing = (0..triangles).filter(|triangle|
+            {
+                let corners = &self.normals[(triangle * 3)..(triangle * 3 + 3)];
+                let normal = corners[0] + corners[1] + corners[2];
+
+                self.face_normal(*triangle).dot(normal) < 0.0
+            }).count();
+
+            return disagreeing * 2 > triangles;
+        }
+
+        let volume: f64 = (0..triangles).map(|triangle|
+        {
+            let p0 = self.vertex(self.indices[triangle * 3]);
+            let p1 = self.vertex(self.indices[triangle * 3 + 1]);
+            let p2 = self.vertex(self.indices[triangle * 3 + 2]);
+
+            p0.dot(p1.cross(p2))
+        }).sum();
+
+        volume < 0.0
+    }
+
+    //merges vertices closer than epsilon (with the same color) and returns the counts before and after
+    //normals and uvs are per corner already so they stay as they are, unless they were generated
+    pub fn weld(&mut self, epsilon: f64) -> (usize, usize)
+    {
+        let before = self.vertices.len() / 3;
+
+        let cell = |point: Point3D| -> (i64, i64, i64)
+        {
+            if epsilon > 0.0
+            {
+                let cell = |value: f64| (value / epsilon).floor() as i64;
+
+                (cell(point.x), cell(point.y), cell(point.z))
+            } else
+            {
+                //adding 0 turns -0 into 0 so they end up in the same cell
+                let bits = |value: f64| (value + 0.0).to_bits() as i64;
+
+                (bits(point.x), bits(point.y), bits(point.z))
+            }
+        };
+
+        let same_color = |a: Option<Color>, b: Option<Color>|
+        {
+            match (a, b)
+            {
+                (Some(a), Some(b)) => a.r == b.r && a.g == b.g && a.b == b.b,
+                (None, None) => true,
+                _ => false
+            }
+        };
+
+        //close vertices can land in the next cell over so those get checked too
+        let neighbors = if epsilon > 0.0
+        {
+            -1..=1
+        } else
+        {
+            0..=0
+        };
+
+        let mut grid: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+
+        let mut positions: Vec<Point3D> = Vec::new();
+        let mut colors: Vec<Option<Color>> = Vec::new();
+
+        let remap = (0..before).map(|index|
+        {
+            let point = self.vertex(index);
+            let color = self.vertex_colors.get(index).copied().flatten();
+
+            let (x, y, z) = cell(point);
+
+            let mut found = None;
+            for dx in neighbors.clone()
+            {
+                for dy in neighbors.clone()
+                {
+                    for dz in neighbors.clone()
+                    {
+                        let Some(candidates) = grid.get(&(x + dx, y + dy, z + dz)) else { continue };
+
+                        found = found.or_else(|| candidates.iter().copied().find(|candidate|
+                        {
+                            positions[*candidate].distance(point) <= epsilon
+                                && same_color(colors[*candidate], color)
+                        }));
+                    }
+                }
+            }
+
+            found.unwrap_or_else(||
+            {
+                let new_index = positions.len();
+
+                positions.push(point);
+                colors.push(color);
+
+                grid.entry((x, y, z)).or_default().push(new_index);
+
+                new_index
+            })
+        }).collect::<Vec<usize>>();
+
+        let after = positions.len();
+
+        self.vertices = positions.into_iter().flat_map(|position|
+        {
+            [position.x, position.y, position.z]
+        }).collect();
+
+        if !self.vertex_colors.is_empty()
+        {
+            self.vertex_colors = colors;
+        }
+
+        for index in self.indices.iter_mut().chain(self.line_indices.iter_mut())
+        {
+            *index = remap[*index];
+        }
+
+        //welded vertices can finally share their normals
+        if self.generated_normals
+        {
+            self.generate_normals();
+        }
+
+        if !self.tangents.is_empty()
+        {
+            self.generate_tangents();
+        }
+
+        (before, after)
+    }
+
+    pub fn generate_normals(&mut self)
+    {
+        self.generated_normals = true;
+
+        let mut vertex_normals: HashMap<(usize, u32), Point3D> = HashMap::new();
+
+        for triangle in 0..(self.indices.len() / 3)
+        {
+            let group = if let Some(group) = self.smoothing_group(triangle)
+            {
+                group
+            } else
+            {
+                continue;
+            };
+
+            let normal = self.face_normal(triangle);
+
+            for index in &self.indices[(triangle * 3)..(triangle * 3 + 3)]
+            {
+                let vertex_normal = vertex_normals.entry((*index, group))
+                    .or_insert(Point3D{x: 0.0, y: 0.0, z: 0.0});
+
+                *vertex_normal = *vertex_normal + normal;
+            }
+        }
+
+        self.normals = self.indices.iter().enumerate().map(|(meta_index, index)|
+        {
+            let triangle = meta_index / 3;
+
+            let normal = match self.smoothing_group(triangle)
+            {
+                Some(group) => vertex_normals[&(*index, group)],
+                None => self.face_normal(triangle)
+            };
+
+            if normal.dot(normal) > 0.0
+            {
+                normal.normalized()
+            } else
+            {
+                normal
+            }
+        }).collect();
+    }
+
+    //tangents point where u grows along the surface, one for every index like the normals
+    //without uvs theres no u so they just get any direction perpendicular to the normal
+    pub fn generate_tangents(&mut self)
+    {
+        if self.normals.is_empty()
+        {
+            self.generate_normals();
+        }
+
+        if self.uvs.is_empty()
+        {
+            self.tangents = self.normals.iter().map(|normal| Self::perpendicular(*normal)).collect();
+
+            return;
+        }
+
+        //vertices only share tangents if they share uvs too, otherwise seams get smeared
+        let key = |model: &Self, meta_index: usize, group: u32|
+        {
+            let uv = model.uvs[meta_index];
+
+            (model.indices[meta_index], group, uv.x.to_bits(), uv.y.to_bits())
+        };
+
+        let mut vertex_tangents: HashMap<(usize, u32, u64, u64), Point3D> = HashMap::new();
+
+        let face_tangents = (0..(self.indices.len() / 3)).map(|triangle|
+        {
+            let meta_index = triangle * 3;
+
+            let p0 = self.vertex(self.indices[meta_index]);
+            let p1 = self.vertex(self.indices[meta_index + 1]);
+            let p2 = self.vertex(self.indices[meta_index + 2]);
+
+            let uv0 = self.uvs[meta_index];
+            let duv1 = self.uvs[meta_index + 1] - uv0;
+            let duv2 = self.uvs[meta_index + 2] - uv0;
+
+            let determinant = duv1.x * duv2.y - duv2.x * duv1.y;
+
+            //squished uvs dont have any direction
+            if determinant.abs() < 0.0000001
+            {
+                return Point3D{x: 0.0, y: 0.0, z: 0.0};
+            }
+
+            ((p1 - p0) * duv2.y - (p2 - p0) * duv1.y) / determinant
+        }).collect::<Vec<Point3D>>();
+
+        for (triangle, tangent) in face_tangents.iter().enumerate()
+        {
+            let group = if let Some(group) = self.smoothing_group(triangle)
+            {
+                group
+            } else
+            {
+                continue;
+            };
+
+            for meta_index in (triangle * 3)..(triangle * 3 + 3)
+            {
+                let vertex_tangent = vertex_tangents.entry(key(self, meta_index, group))
+                    .or_insert(Point3D{x: 0.0, y: 0.0, z: 0.0});
+
+                *vertex_tangent = *vertex_tangent + *tangent;
+            }
+        }
+
+        self.tangents = (0..self.indices.len()).map(|meta_index|
+        {
+            let triangle = meta_index / 3;
+
+            let tangent = match self.smoothing_group(triangle)
+            {
+                Some(group) => vertex_tangents[&key(self, meta_index, group)],
+                None => face_tangents[triangle]
+            };
+
+            //removes the part along the normal so the tangent lies flat on the surface
+            let normal = self.normals[meta_index];
+            let normal = if normal.dot(normal) > 0.0
+            {
+                normal.normalized()
+            } else
+            {
+                normal
+            };
+
+            let tangent = tangent - normal * normal.dot(tangent);
+
+            if tangent.dot(tangent) > 0.0000001
+            {
+                tangent.normalized()
+            } else
+            {
+                Self::perpendicular(normal)
+            }
+        }).collect();
+    }
+
+    fn perpendicular(normal: Point3D) -> Point3D
+    {
+        //crossing with the axis least aligned with the normal keeps it from collapsing
+        let axis = if normal.x.abs() < 0.9
+        {
+            Point3D{x: 1.0, y: 0.0, z: 0.0}
+        } else
+        {
+            Point3D{x: 0.0, y: 1.0, z: 0.0}
+        };
+
+        let tangent = axis - normal * normal.dot(axis);
+
+        if tangent.dot(tangent) > 0.0
+        {
+            tangent.normalized()
+        } else
+        {
+            axis
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn close_enough(a: Point3D, b: Point3D) -> bool
+    {
+        (a - b).length() < 0.001
+    }
+
+    const QUAD: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    #[test]
+    fn uv_components()
+    {
+        let model = Model::read_obj_str(
+            &format!("{QUAD}vt 0.5\nvt 0.25 0.75 0\nvt 1 1\nf 1/1 2/2 3/3")
+        ).unwrap();
+
+        assert_eq!(model.uvs[0].x, 0.25);
+        assert_eq!(model.uvs[0].y, 0.75);
+        assert_eq!(model.uvs[1].x, 1.0);
+        assert_eq!(model.uvs[2].x, 0.5);
+        assert_eq!(model.uvs[2].y, 0.0);
+
+        assert!(Model::read_obj_str("vt 1 2 3 4").is_err());
+    }
+
+    #[test]
+    fn parallel_matches_serial()
+    {
+        let text = "v 0 0 0 1 0 0\nvt 0 0\nvn 0 0 1\nv 1 0 0 0 1 0\nvt 1 0\ns off\n\
+            v 1 1 0 0 0 1\nvt 1 1\nf 1/1/1 2/2/1 3/3/1\nv 0 1 0\nvn 0 1 1\nf 1/1/2 3/3/2 4/2/1";
+
+        let parallel = Model::read_obj_str(text).unwrap();
+
+        let mut serial = Model::new();
+        let mut parser = ModelParser::new(&mut serial);
+        for line in ModelParser::parse_obj(text)
+        {
+            parser.parse_obj_line(None, line).unwrap();
+        }
+
+        parser.resolve_materials();
+
+        let fields = |model: &Model|
+        {
+            format!(
+                "{:?} {:?} {:?} {:?} {:?} {:?}",
+                model.vertices,
+                model.vertex_colors,
+                model.indices,
+                model.normals,
+                model.uvs,
+                model.smoothing_groups
+            )
+        };
+
+        assert_eq!(fields(&parallel), fields(&serial));
+    }
+
+    #[test]
+    fn texture_paths()
+    {
+        let obj_dir = std::env::temp_dir().join(format!("rendererthingy_paths_{}", std::process::id()));
+        let mtl_dir = obj_dir.join("materials");
+        std::fs::create_dir_all(&mtl_dir).unwrap();
+
+        std::fs::write(obj_dir.join("beside_obj.png"), []).unwrap();
+        std::fs::write(mtl_dir.join("beside_mtl.png"), []).unwrap();
+
+        let directories = [obj_dir.as_path(), mtl_dir.as_path()];
+
+        let mut model = Model::new();
+        let parser = ModelParser::new(&mut model);
+
+        let resolved = parser.resolve_path("C:\\Users\\someone\\beside_obj.png", &directories);
+        assert_eq!(resolved, obj_dir.join("beside_obj.png"));
+
+        let resolved = parser.resolve_path("./beside_mtl.png", &directories);
+        assert_eq!(resolved, mtl_dir.join("./beside_mtl.png"));
+
+        let resolved = parser.resolve_path("materials/beside_mtl.png", &directories);
+        assert_eq!(resolved, obj_dir.join("materials/beside_mtl.png"));
+
+        let resolved = parser.resolve_path("missing.png", &directories);
+        assert_eq!(resolved, obj_dir.join("missing.png"));
+
+        std::fs::remove_dir_all(&obj_dir).unwrap();
+    }
+
+    #[test]
+    fn zipped_model()
+    {
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("rendererthingy_zipped_{}.zip", std::process::id()));
+
+        {
+            let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+            let options = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+
+            let files = [
+                ("cube/cube.obj", format!("mtllib materials/cube.mtl\n{QUAD}vt 0 0\nusemtl red\nf 1/1 2/1 3/1")),
+                ("cube/materials/cube.mtl", "newmtl red\nKd 1 0 0\nmap_Kd D:\\exports\\red.ppm".to_owned()),
+                ("cube/red.ppm", "P3 1 1 255 255 0 0".to_owned())
+            ];
+
+            for (name, contents) in files
+            {
+                writer.start_file(name, options).unwrap();
+                writer.write_all(contents.as_bytes()).unwrap();
+            }
+
+            writer.finish().unwrap();
+        }
+
+        let model = Model::read_obj(path.to_str().unwrap());
+
+        std::fs::remove_file(&path).unwrap();
+
+        let model = model.unwrap();
+
+        assert_eq!(model.materials.len(), 1);
+        assert!(model.materials[0].diffuse_texture.is_some());
+        assert!(model.warnings.is_empty());
+    }
+
+    #[test]
+    fn missing_texture_warning()
+    {
+        let directory = std::env::temp_dir().join(format!("rendererthingy_missing_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        std::fs::write(directory.join("model.mtl"), "newmtl red\nKd 1 0 0\nmap_Kd nowhere.png").unwrap();
+        std::fs::write(
+            directory.join("model.obj"),
+            format!("mtllib model.mtl\n{QUAD}usemtl red\nf 1 2 3")
+        ).unwrap();
+
+        let model = Model::read_obj(directory.join("model.obj").to_str().unwrap());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let model = model.unwrap();
+
+        assert_eq!(model.warnings.len(), 1);
+        assert!(model.warnings[0].contains("nowhere.png"), "{}", model.warnings[0]);
+
+        assert!(model.materials[0].diffuse_texture.is_none());
+        assert_eq!(model.materials[0].diffuse_color.map(|color| color.r), Some(1.0));
+    }
+
+    #[test]
+    fn weld_vertices()
+    {
+        //a quad split into 2 triangles that dont share any vertices, one corner is slightly off
+        let text = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 0 0\nv 1 1.0001 0\nv 0 1 0\nf 1 2 3\nf 4 5 6";
+
+        let mut model = Model::read_obj_str(text).unwrap();
+        assert_eq!(model.weld(0.0), (6, 5));
+
+        //negative zero is the same point as zero
+        let mut model = Model::read_obj_str("v 0 0 0\nv -0 0 0\nv 1 1 1\nf 1 2 3").unwrap();
+        assert_eq!(model.weld(0.0), (3, 2));
+
+        let mut model = Model::read_obj_str(text).unwrap();
+        assert_eq!(model.weld(0.001), (6, 4));
+
+        assert_eq!(model.indices, [1, 2, 0, 2, 3, 0]);
+        assert!(close_enough(model.vertex(3), Point3D{x: 0.0, y: 1.0, z: 0.0}));
+
+        //close points in neighboring cells still get merged
+        let mut model = Model::read_obj_str("v 0.0999 0 0\nv 0.1001 0 0\nv 1 1 1\nf 1 2 3").unwrap();
+        assert_eq!(model.weld(0.1), (3, 2));
+
+        //different colors keep their own vertices
+        let mut model = Model::read_obj_str("v 0 0 0 1 0 0\nv 0 0 0 0 1 0\nv 1 1 1 0 0 1\nf 1 2 3").unwrap();
+        assert_eq!(model.weld(0.1), (3, 3));
+    }
+
+    #[test]
+    fn usemtl_before_mtllib()
+    {
+        let directory = std::env::temp_dir().join(format!("rendererthingy_usemtl_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        std::fs::write(directory.join("model.mtl"), "newmtl blue\nKd 0 0 1\nnewmtl red\nKd 1 0 0").unwrap();
+        std::fs::write(
+            directory.join("model.obj"),
+            format!("{QUAD}usemtl red\nf 1 2 3\nusemtl missing\nf 1 3 4\nmtllib model.mtl\nusemtl blue\nf 2 3 4")
+        ).unwrap();
+
+        let model = Model::read_obj(directory.join("model.obj").to_str().unwrap());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let model = model.unwrap();
+
+        assert_eq!(model.material_indices, [Some(1), None, Some(0)]);
+        assert_eq!(model.materials[1].diffuse_color.map(|color| color.r), Some(1.0));
+    }
+
+    #[test]
+    fn parallel_textures()
+    {
+        let directory = std::env::temp_dir().join(format!("rendererthingy_textures_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let colors = ["255 0 0", "0 255 0", "0 0 255", "255 255 0"];
+        for (index, color) in colors.iter().enumerate()
+        {
+            std::fs::write(directory.join(format!("{index}.ppm")), format!("P3 1 1 255 {color}")).unwrap();
+        }
+
+        //the textures are spread over two mtllibs and one gets shared
+        std::fs::write(directory.join("a.mtl"), "newmtl a\nmap_Kd 0.ppm\nnewmtl b\nmap_Kd 1.ppm").unwrap();
+        std::fs::write(
+            directory.join("b.mtl"),
+            "newmtl c\nmap_Kd 2.ppm\nnewmtl d\nmap_Kd 3.ppm\nnewmtl e\nmap_Kd 0.ppm"
+        ).unwrap();
+
+        let faces = ["a", "b", "c", "d", "e"].map(|name| format!("usemtl {name}\nf 1 2 3\n")).concat();
+        std::fs::write(
+            directory.join("model.obj"),
+            format!("mtllib a.mtl\nmtllib b.mtl\n{QUAD}{faces}")
+        ).unwrap();
+
+        let model = Model::read_obj(directory.join("model.obj").to_str().unwrap());
+
+        let serial = [0, 1, 2, 3, 0].map(|index|
+        {
+            Texture::load(&directory.join(format!("{index}.ppm")), ColorSpace::Srgb).unwrap()
+        });
+
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let model = model.unwrap();
+
+        let uv = Point2D{x: 0.5, y: 0.5};
+        for (material, serial) in model.materials.iter().zip(serial.iter())
+        {
+            let color = material.diffuse_texture.as_ref().unwrap().pixel(uv);
+            let expected = serial.pixel(uv);
+
+            assert_eq!((color.r, color.g, color.b), (expected.r, expected.g, expected.b));
+        }
+
+        assert_eq!(model.materials.len(), 5);
+        assert!(Rc::ptr_eq(
+            model.materials[0].diffuse_texture.as_ref().unwrap(),
+            model.materials[4].diffuse_texture.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn zero_shininess()
+    {
+        let directory = std::env::temp_dir().join(format!("rendererthingy_shininess_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        std::fs::write(directory.join("model.mtl"), "newmtl matte\nNs 0\nnewmtl shiny\nNs 50").unwrap();
+        std::fs::write(
+            directory.join("model.obj"),
+            format!("mtllib model.mtl\n{QUAD}usemtl matte\nf 1 2 3\nusemtl shiny\nf 1 3 4")
+        ).unwrap();
+
+        let model = Model::read_obj(directory.join("model.obj").to_str().unwrap());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let model = model.unwrap();
+
+        assert_eq!(model.materials[0].shininess, Some(1.0));
+        assert_eq!(model.materials[1].shininess, Some(50.0));
+    }
+
+    #[test]
+    fn non_finite_values()
+    {
+        let error = |text: &str|
+        {
+            match Model::read_obj_str(text)
+            {
+                Err(ModelError{line_index, error_type: ModelErrorType::ParsingError(value)}) =>
+                {
+                    Some((line_index, value))
+                },
+                _ => None
+            }
+        };
+
+        assert_eq!(error("v 0 0 0\nv nan 1 0"), Some((Some(1), "nan".to_owned())));
+        assert_eq!(error("vn 0 inf 0"), Some((Some(0), "inf".to_owned())));
+        assert_eq!(error("vt -infinity 0"), Some((Some(0), "-infinity".to_owned())));
+
+        assert!(Model::read_obj_str(&format!("{QUAD}f 1 2 3")).is_ok());
+    }
+
+    #[test]
+    fn face_indices_out_of_range()
+    {
+        let malformed = |text: &str|
+        {
+            matches!(
+                Model::read_obj_str(text),
+                Err(ModelError{line_index: Some(_), error_type: ModelErrorType::MalformedObj})
+            )
+        };
+
+        assert!(malformed(&format!("{QUAD}f 1 2 5")));
+        assert!(malformed(&format!("{QUAD}f 0 1 2")));
+        assert!(malformed(&format!("{QUAD}f -5 1 2")));
+        assert!(malformed(&format!("{QUAD}vt 0 0\nf 1/1 2/2 3/2")));
+        assert!(malformed(&format!("{QUAD}vn 0 0 1\nf 1//1 2//1 3//2")));
+
+        let model = Model::read_obj_str(&format!("{QUAD}vn 0 0 1\nf -4//1 -3//1 -2//-1")).unwrap();
+        assert_eq!(model.indices, vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn degenerate_models()
+    {
+        let empty = Model::read_obj_str("# nothing here\n").unwrap();
+        assert!(empty.indices.is_empty());
+
+        let only_vertices = Model::read_obj_str(QUAD).unwrap();
+        assert!(only_vertices.indices.is_empty());
+
+        let mixed_normals = Model::read_obj_str(&format!("{QUAD}vn 0 0 1\nf 1//1 2//1 3//1\nf 1 3 4"));
+        assert!(matches!(
+            mixed_normals,
+            Err(ModelError{line_index: None, error_type: ModelErrorType::MalformedObj})
+        ));
+
+        let mut model = Model::read_obj_str(&format!("{QUAD}f 1 2 3")).unwrap();
+        model.indices.push(0);
+        assert!(model.validate().is_err());
+
+        model.indices.truncate(3);
+        model.indices[0] = 4;
+        assert!(model.validate().is_err());
+    }
+
+    #[test]
+    fn concave_face()
+    {
+        //an l shape starting next to the inner corner, fanning it covers the missing square
+        let model = Model::read_obj_str(
+            "v 2 1 0\nv 1 1 0\nv 1 2 0\nv 0 2 0\nv 0 0 0\nv 2 0 0\nf 1 2 3 4 5 6"
+        ).unwrap();
+
+        assert_eq!(model.indices.len(), 4 * 3);
+
+        let areas = model.indices.chunks(3).map(|triangle|
+        {
+            let [a, b, c] = [0, 1, 2].map(|index| model.vertex(triangle[index]));
+
+            (b - a).cross(c - a).z / 2.0
+        }).collect::<Vec<f64>>();
+
+        assert!(areas.iter().all(|area| *area > 0.0));
+        assert!((areas.iter().sum::<f64>() - 3.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn tangents_quad()
+    {
+        let mut model = Model::read_obj_str(
+            &format!("{QUAD}vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nf 1/1 2/2 3/3 4/4")
+        ).unwrap();
+
+        model.generate_tangents();
+
+        assert_eq!(model.tangents.len(), model.indices.len());
+        assert!(model.tangents.iter().all(|tangent|
+        {
+            close_enough(*tangent, Point3D{x: 1.0, y: 0.0, z: 0.0})
+        }));
+
+        //u going up the quad instead of across it
+        let mut model = Model::read_obj_str(
+            &format!("{QUAD}vt 0 1\nvt 0 0\nvt 1 0\nvt 1 1\nf 1/1 2/2 3/3 4/4")
+        ).unwrap();
+
+        model.generate_tangents();
+
+        assert!(model.tangents.iter().all(|tangent|
+        {
+            close_enough(*tangent, Point3D{x: 0.0, y: 1.0, z: 0.0})
+        }));
+    }
+
+    #[test]
+    fn tangents_without_uvs()
+    {
+        let mut model = Model::read_obj_str(&format!("{QUAD}f 1 2 3 4")).unwrap();
+
+        model.generate_tangents();
+
+        assert_eq!(model.tangents.len(), model.indices.len());
+        for (tangent, normal) in model.tangents.iter().zip(model.normals.iter())
+        {
+            assert!((tangent.length() - 1.0).abs() < 0.001);
+            assert!(tangent.dot(*normal).abs() < 0.001);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf, Component},
+    io::{self, Read},
+    fs::File
+};
+
+use zip::ZipArchive;
+
+use super::ModelErrorType;
+
+
+//a zipped model with everything it needs, all of it gets read into memory up front
+pub struct Archive
+{
+    files: HashMap<PathBuf, Vec<u8>>
+}
+
+impl Archive
+{
+    pub fn open(path: &Path) -> Result<Self, ModelErrorType>
+    {
+        let mut archive = ZipArchive::new(File::open(path)?).map_err(ModelErrorType::Archive)?;
+
+        let mut files = HashMap::new();
+        for index in 0..archive.len()
+        {
+            let mut file = archive.by_index(index).map_err(ModelErrorType::Archive)?;
+
+            if !file.is_file()
+            {
+                continue;
+            }
+
+            //names with .. or absolute paths dont get a path and are skipped
+            if let Some(name) = file.enclosed_name().map(Self::normalized)
+            {
+                let mut data = Vec::with_capacity(file.size() as usize);
+                file.read_to_end(&mut data)?;
+
+                files.insert(name, data);
+            }
+        }
+
+        Ok(Self{files})
+    }
+
+    //the shallowest obj, so a preview model in some subfolder doesnt win
+    pub fn obj_path(&self) -> Option<&Path>
+    {
+        self.files.keys().filter(|path|
+        {
+            path.extension().map(|extension| extension.eq_ignore_ascii_case("obj")).unwrap_or(false)
+        }).min_by_key(|path| (path.components().count(), path.as_os_str().to_owned()))
+            .map(|path| path.as_path())
+    }
+
+    pub fn contains(&self, path: &Path) -> bool
+    {
+        self.files.contains_key(&Self::normalized(path))
+    }
+
+    pub fn read(&self, path: &Path) -> io::Result<&[u8]>
+    {
+        self.files.get(&Self::normalized(path)).map(|data| data.as_slice()).ok_or_else(||
+        {
+            io::Error::new(io::ErrorKind::NotFound, format!("{} isnt in the archive", path.display()))
+        })
+    }
+
+    //mtl files love writing ./ and .. so paths get flattened before looking them up
+    fn normalized(path: &Path) -> PathBuf
+    {
+        let mut normalized = PathBuf::new();
+
+        for component in path.components()
+        {
+            match component
+            {
+                Component::Normal(part) => normalized.push(part),
+                Component::ParentDir =>
+                {
+                    normalized.pop();
+                },
+                _ => ()
+            }
+        }
+
+        normalized
+    }
+}
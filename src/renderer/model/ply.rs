@@ -0,0 +1,537 @@
+use std::{
+    str,
+    io::Read,
+    fs::File
+};
+
+use crate::renderer::common::{Color, Point2D, Point3D};
+
+use super::{Model, ModelError, ModelErrorType};
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PlyFormat
+{
+    Ascii,
+    BinaryLittleEndian
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PlyType
+{
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64
+}
+
+impl PlyType
+{
+    fn parse(name: &str) -> Result<Self, ModelErrorType>
+    {
+        match name
+        {
+            "char" | "int8" => Ok(PlyType::Int8),
+            "uchar" | "uint8" => Ok(PlyType::UInt8),
+            "short" | "int16" => Ok(PlyType::Int16),
+            "ushort" | "uint16" => Ok(PlyType::UInt16),
+            "int" | "int32" => Ok(PlyType::Int32),
+            "uint" | "uint32" => Ok(PlyType::UInt32),
+            "float" | "float32" => Ok(PlyType::Float32),
+            "double" | "float64" => Ok(PlyType::Float64),
+            _ => Err(ModelErrorType::ParsingError(name.to_owned()))
+        }
+    }
+
+    fn size(self) -> usize
+    {
+        match self
+        {
+            PlyType::Int8 | PlyType::UInt8 => 1,
+            PlyType::Int16 | PlyType::UInt16 => 2,
+            PlyType::Int32 | PlyType::UInt32 | PlyType::Float32 => 4,
+            PlyType::Float64 => 8
+        }
+    }
+
+    fn color_scale(self) -> f64
+    {
+        match self
+        {
+            PlyType::Int8 => i8::MAX as f64,
+            PlyType::UInt8 => u8::MAX as f64,
+            PlyType::Int16 => i16::MAX as f64,
+            PlyType::UInt16 => u16::MAX as f64,
+            PlyType::Int32 => i32::MAX as f64,
+            PlyType::UInt32 => u32::MAX as f64,
+            PlyType::Float32 | PlyType::Float64 => 1.0
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum PlyProperty
+{
+    Scalar{name: String, value_type: PlyType},
+    List{name: String, count_type: PlyType, value_type: PlyType}
+}
+
+#[derive(Debug, Clone)]
+struct PlyElement
+{
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>
+}
+
+impl PlyElement
+{
+    fn scalar_index(&self, names: &[&str]) -> Option<usize>
+    {
+        self.properties.iter().position(|property|
+        {
+            match property
+            {
+                PlyProperty::Scalar{name, ..} => names.contains(&name.as_str()),
+                _ => false
+            }
+        })
+    }
+
+    fn scalar_type(&self, index: usize) -> Option<PlyType>
+    {
+        match self.properties[index]
+        {
+            PlyProperty::Scalar{value_type, ..} => Some(value_type),
+            _ => None
+        }
+    }
+}
+
+enum PlyValues<'a>
+{
+    Ascii(str::SplitAsciiWhitespace<'a>),
+    Binary(&'a [u8])
+}
+
+impl<'a> PlyValues<'a>
+{
+    fn next(&mut self, value_type: PlyType) -> Result<f64, ModelErrorType>
+    {
+        match self
+        {
+            PlyValues::Ascii(values) =>
+            {
+                let value = values.next().ok_or(ModelErrorType::MissingValue)?;
+
+                value.parse().map_err(|_| ModelErrorType::ParsingError(value.to_owned()))
+            },
+            PlyValues::Binary(bytes) =>
+            {
+                let size = value_type.size();
+                if bytes.len() < size
+                {
+                    return Err(ModelErrorType::MissingValue);
+                }
+
+                let (value, rest) = bytes.split_at(size);
+                *bytes = rest;
+
+                let value = match value_type
+                {
+                    PlyType::Int8 => value[0] as i8 as f64,
+                    PlyType::UInt8 => value[0] as f64,
+                    PlyType::Int16 => i16::from_le_bytes([value[0], value[1]]) as f64,
+                    PlyType::UInt16 => u16::from_le_bytes([value[0], value[1]]) as f64,
+                    PlyType::Int32 => i32::from_le_bytes(value.try_into().unwrap()) as f64,
+                    PlyType::UInt32 => u32::from_le_bytes(value.try_into().unwrap()) as f64,
+                    PlyType::Float32 => f32::from_le_bytes(value.try_into().unwrap()) as f64,
+                    PlyType::Float64 => f64::from_le_bytes(value.try_into().unwrap())
+                };
+
+                Ok(value)
+            }
+        }
+    }
+}
+
+struct PlyHeader
+{
+    format: PlyFormat,
+    elements: Vec<PlyElement>
+}
+
+impl PlyHeader
+{
+    fn parse(header: &str) -> Result<Self, ModelErrorType>
+    {
+        let mut lines = header.lines();
+
+        if lines.next().map(|line| line.trim()) != Some("ply")
+        {
+            return Err(ModelErrorType::MalformedPly);
+        }
+
+        let mut format = None;
+        let mut elements: Vec<PlyElement> = Vec::new();
+
+        for line in lines
+        {
+            let mut values = line.split_ascii_whitespace();
+
+            let mut next_value = || values.next().ok_or(ModelErrorType::MissingValue);
+            match next_value()?
+            {
+                "format" =>
+                {
+                    format = match next_value()?
+                    {
+                        "ascii" => Some(PlyFormat::Ascii),
+                        "binary_little_endian" => Some(PlyFormat::BinaryLittleEndian),
+                        value => return Err(ModelErrorType::ParsingError(value.to_owned()))
+                    };
+                },
+                "element" =>
+                {
+                    let name = next_value()?.to_owned();
+
+                    let count = next_value()?;
+                    let count = count.parse()
+                        .map_err(|_| ModelErrorType::ParsingError(count.to_owned()))?;
+
+                    elements.push(PlyElement{name, count, properties: Vec::new()});
+                },
+                "property" =>
+                {
+                    let property = match next_value()?
+                    {
+                        "list" =>
+                        {
+                            let count_type = PlyType::parse(next_value()?)?;
+                            let value_type = PlyType::parse(next_value()?)?;
+
+                            PlyProperty::List{name: next_value()?.to_owned(), count_type, value_type}
+                        },
+                        value_type =>
+                        {
+                            let value_type = PlyType::parse(value_type)?;
+
+                            PlyProperty::Scalar{name: next_value()?.to_owned(), value_type}
+                        }
+                    };
+
+                    elements.last_mut().ok_or(ModelErrorType::MalformedPly)?
+                        .properties.push(property);
+                },
+                _ => ()
+            }
+        }
+
+        let format = format.ok_or(ModelErrorType::MalformedPly)?;
+
+        Ok(PlyHeader{format, elements})
+    }
+}
+
+pub fn read_ply(filename: &str) -> Result<Model, ModelError>
+{
+    let mut data = Vec::new();
+    File::open(filename)?.read_to_end(&mut data)?;
+
+    parse(&data).map_err(|error_type| ModelError{line_index: None, error_type})
+}
+
+fn parse(data: &[u8]) -> Result<Model, ModelErrorType>
+{
+    //going line by line so a comment mentioning end_header doesnt cut the header short
+    let mut header_length = 0;
+    let mut body_start = None;
+    for line in data.split_inclusive(|byte| *byte == b'\n')
+    {
+        if line.trim_ascii() == b"end_header"
+        {
+            body_start = Some(header_length + line.len());
+            break;
+        }
+
+        header_length += line.len();
+    }
+
+    let body_start = body_start.ok_or(ModelErrorType::MalformedPly)?;
+
+    let header = str::from_utf8(&data[..header_length])
+        .map_err(|_| ModelErrorType::MalformedPly)?;
+
+    let header = PlyHeader::parse(header)?;
+
+    let body = &data[body_start..];
+
+    let mut values = match header.format
+    {
+        PlyFormat::Ascii =>
+        {
+            let body = str::from_utf8(body).map_err(|_| ModelErrorType::MalformedPly)?;
+
+            PlyValues::Ascii(body.split_ascii_whitespace())
+        },
+        PlyFormat::BinaryLittleEndian => PlyValues::Binary(body)
+    };
+
+    let mut model = Model::new();
+
+    let mut positions: Vec<Point3D> = Vec::new();
+    let mut normals: Vec<Point3D> = Vec::new();
+    let mut uvs: Vec<Point2D> = Vec::new();
+
+    for element in header.elements.iter()
+    {
+        match element.name.as_str()
+        {
+            "vertex" =>
+            {
+                let position_indices = [
+                    element.scalar_index(&["x"]),
+                    element.scalar_index(&["y"]),
+                    element.scalar_index(&["z"])
+                ];
+
+                let normal_indices = [
+                    element.scalar_index(&["nx"]),
+                    element.scalar_index(&["ny"]),
+                    element.scalar_index(&["nz"])
+                ];
+
+                let uv_indices = [
+                    element.scalar_index(&["s", "u", "texture_u"]),
+                    element.scalar_index(&["t", "v", "texture_v"])
+                ];
+
+                let color_indices = [
+                    element.scalar_index(&["red", "r"]),
+                    element.scalar_index(&["green", "g"]),
+                    element.scalar_index(&["blue", "b"])
+                ];
+
+                for _ in 0..element.count
+                {
+                    let properties = read_element(&mut values, element)?;
+
+                    let property = |index: Option<usize>| -> Result<f64, ModelErrorType>
+                    {
+                        index.map(|index| properties[index]).ok_or(ModelErrorType::MissingValue)
+                    };
+
+                    positions.push(Point3D{
+                        x: property(position_indices[0])?,
+                        y: property(position_indices[1])?,
+                        z: property(position_indices[2])?
+                    });
+
+                    if normal_indices.iter().all(Option::is_some)
+                    {
+                        normals.push(Point3D{
+                            x: property(normal_indices[0])?,
+                            y: property(normal_indices[1])?,
+                            z: property(normal_indices[2])?
+                        }.normalized());
+                    }
+
+                    if uv_indices.iter().all(Option::is_some)
+                    {
+                        uvs.push(Point2D{
+                            x: property(uv_indices[0])?,
+                            y: property(uv_indices[1])?
+                        });
+                    }
+
+                    if color_indices.iter().all(Option::is_some)
+                    {
+                        let component = |index: Option<usize>| -> Result<f64, ModelErrorType>
+                        {
+                            let scale = index.and_then(|index| element.scalar_type(index))
+                                .map(PlyType::color_scale)
+                                .unwrap_or(1.0);
+
+                            Ok(property(index)? / scale)
+                        };
+
+                        model.vertex_colors.push(Some(Color::new(
+                            component(color_indices[0])?,
+                            component(color_indices[1])?,
+                            component(color_indices[2])?
+                        )));
+                    }
+                }
+            },
+            "face" =>
+            {
+                let indices_name = |name: &str| name == "vertex_indices" || name == "vertex_index";
+
+                for _ in 0..element.count
+                {
+                    let mut face = Vec::new();
+
+                    for property in element.properties.iter()
+                    {
+                        match property
+                        {
+                            PlyProperty::List{name, count_type, value_type} =>
+                            {
+                                let count = values.next(*count_type)? as usize;
+                                let list = (0..count).map(|_| values.next(*value_type))
+                                    .collect::<Result<Vec<f64>, _>>()?;
+
+                                if indices_name(name)
+                                {
+                                    face = list.into_iter().map(vertex_index)
+                                        .collect::<Result<Vec<usize>, _>>()?;
+                                }
+                            },
+                            PlyProperty::Scalar{value_type, ..} =>
+                            {
+                                values.next(*value_type)?;
+                            }
+                        }
+                    }
+
+                    if face.len() < 3 || face.iter().any(|index| *index >= positions.len())
+                    {
+                        return Err(ModelErrorType::MalformedPly);
+                    }
+
+                    let mut insert_face = |index: usize|
+                    {
+                        model.indices.push(index);
+
+                        if !uvs.is_empty()
+                        {
+                            model.uvs.push(uvs[index]);
+                        }
+
+                        if !normals.is_empty()
+                        {
+                            model.normals.push(normals[index]);
+                        }
+                    };
+
+                    for v in 2..face.len()
+                    {
+                        insert_face(face[v - 1]);
+                        insert_face(face[v]);
+                        insert_face(face[0]);
+
+                        model.material_indices.push(None);
+                    }
+                }
+            },
+            _ =>
+            {
+                for _ in 0..element.count
+                {
+                    read_element(&mut values, element)?;
+                }
+            }
+        }
+    }
+
+    model.vertices = positions.into_iter().flat_map(|position|
+    {
+        [position.x, position.y, position.z]
+    }).collect();
+
+    Ok(model)
+}
+
+//the values all come out as floats, a negative one would wrap around as an index
+fn vertex_index(value: f64) -> Result<usize, ModelErrorType>
+{
+    if value >= 0.0 && value.fract() == 0.0
+    {
+        Ok(value as usize)
+    } else
+    {
+        Err(ModelErrorType::MalformedPly)
+    }
+}
+
+fn read_element(values: &mut PlyValues, element: &PlyElement) -> Result<Vec<f64>, ModelErrorType>
+{
+    element.properties.iter().map(|property|
+    {
+        match property
+        {
+            PlyProperty::Scalar{value_type, ..} => values.next(*value_type),
+            PlyProperty::List{count_type, value_type, ..} =>
+            {
+                //lists are skipped, only faces care about them
+                let count = values.next(*count_type)? as usize;
+                for _ in 0..count
+                {
+                    values.next(*value_type)?;
+                }
+
+                Ok(0.0)
+            }
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const TRIANGLE: [f64; 9] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+
+    fn header(format: &str) -> String
+    {
+        format!("ply\nformat {format} 1.0\ncomment made for end_header tests\n\
+            element vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+            element face 1\nproperty list uchar int vertex_indices\nend_header\n")
+    }
+
+    #[test]
+    fn ascii()
+    {
+        let text = header("ascii") + "0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n";
+
+        let model = parse(text.as_bytes()).unwrap();
+
+        assert_eq!(model.vertices, TRIANGLE);
+        assert_eq!(model.indices, [1, 2, 0]);
+    }
+
+    #[test]
+    fn binary()
+    {
+        let mut data = header("binary_little_endian").into_bytes();
+
+        for value in TRIANGLE
+        {
+            data.extend((value as f32).to_le_bytes());
+        }
+
+        data.push(3);
+        for index in [0_i32, 1, 2]
+        {
+            data.extend(index.to_le_bytes());
+        }
+
+        let model = parse(&data).unwrap();
+
+        assert_eq!(model.vertices, TRIANGLE);
+        assert_eq!(model.indices, [1, 2, 0]);
+    }
+
+    #[test]
+    fn negative_index()
+    {
+        let text = header("ascii") + "0 0 0\n1 0 0\n0 1 0\n3 0 -1 2\n";
+
+        assert!(matches!(parse(text.as_bytes()), Err(ModelErrorType::MalformedPly)));
+    }
+}
\ No newline at end of file
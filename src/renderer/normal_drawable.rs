@@ -1,10 +1,15 @@
-use drawable::Drawable;
+use drawable::{Drawable, Rasterizer};
 
 use crate::renderer::common::{
     Point,
+    Point3D,
     Color,
+    DepthOfField,
+    Outline,
+    Region,
     ShaderValue,
     FaceShader,
+    PixelId,
     PixelInfo,
     INTERPOLATED_ZEROS
 };
@@ -16,12 +21,76 @@ mod color_shader;
 pub trait DrawableDisplay
 {
     fn prepare(&mut self, size: (usize, usize));
-    fn display(&self, size: (usize, usize), colors: &[Color]);
+    fn display(&mut self, size: (usize, usize), colors: &[Color], coverage: &[bool]);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrawPass
+{
+    Full,
+    //only fills the depth buffer
+    DepthOnly,
+    //shades the pixels that won the depth pass
+    Shading
+}
+
+//on the screen and in the region, if theres one
+fn inside(size: (usize, usize), region: Option<Region>, point: &Point<usize>) -> bool
+{
+    if point.x >= size.0 || point.y >= size.1
+    {
+        return false;
+    }
+
+    region.map(|region| region.contains(point.x, size.1 - point.y - 1)).unwrap_or(true)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepthFunc
+{
+    Less,
+    LessEqual,
+    Equal,
+    //with the depth buffer already filled this only draws where something else is in front
+    Greater,
+    Always
+}
+
+impl DepthFunc
+{
+    pub fn passes(self, depth: f64, stored: f64) -> bool
+    {
+        match self
+        {
+            DepthFunc::Less => depth < stored,
+            DepthFunc::LessEqual => depth <= stored,
+            DepthFunc::Equal => depth == stored,
+            DepthFunc::Greater => depth > stored,
+            DepthFunc::Always => true
+        }
+    }
 }
 
 pub trait DrawSurface<'a>: Drawable<'a>
 {
     fn display(self);
+
+    //how many times the color shader ran (or will run when displaying) so far
+    fn pixels_shaded(&self) -> usize;
+
+    //surfaces that dont shade while drawing dont care about passes
+    fn set_pass(&mut self, _pass: DrawPass) {}
+
+    //whether drawing in passes changes anything, otherwise a prepass would just draw everything twice
+    fn uses_passes(&self) -> bool
+    {
+        false
+    }
+
+    //how new pixels get compared against the stored depth, the buffer starts cleared to 1
+    fn set_depth_func(&mut self, depth_func: DepthFunc);
+
+    fn depth_func(&self) -> DepthFunc;
 }
 
 pub trait DrawableNormal
@@ -33,6 +102,12 @@ pub trait DrawableNormal
 pub struct NormalDrawable<T>
 {
     size: (usize, usize),
+    region: Option<Region>,
+    rasterizer: Rasterizer,
+    depth_of_field: Option<DepthOfField>,
+    depths: Vec<f64>,
+    colors: Vec<Color>,
+    coverage: Vec<bool>,
     display: T
 }
 
@@ -42,9 +117,36 @@ impl<T> NormalDrawable<T>
     {
         Self{
             size,
+            region: None,
+            rasterizer: Rasterizer::Barycentric,
+            depth_of_field: None,
+            depths: Vec::new(),
+            colors: Vec::new(),
+            coverage: Vec::new(),
             display
         }
     }
+
+    //everything outside of the region is skipped, the frame stays the full size
+    pub fn set_region(&mut self, region: Option<Region>)
+    {
+        self.region = region;
+    }
+
+    pub fn set_rasterizer(&mut self, rasterizer: Rasterizer)
+    {
+        self.rasterizer = rasterizer;
+    }
+
+    pub fn set_depth_of_field(&mut self, depth_of_field: Option<DepthOfField>)
+    {
+        self.depth_of_field = depth_of_field;
+    }
+
+    pub fn into_display(self) -> T
+    {
+        self.display
+    }
 }
 
 impl<T: DrawableDisplay> DrawableNormal for &mut NormalDrawable<T>
@@ -55,11 +157,28 @@ impl<T: DrawableDisplay> DrawableNormal for &mut NormalDrawable<T>
     {
         let total_size = self.size.0 * self.size.1;
 
+        //the buffers stick around between frames so redrawing doesnt allocate
+        self.depths.clear();
+        self.depths.resize(total_size, 1.0);
+
+        self.colors.clear();
+        self.colors.resize(total_size, Color::new(0.0, 0.0, 0.0));
+
+        self.coverage.clear();
+        self.coverage.resize(total_size, false);
+
         NormalSurface{
             size: self.size,
+            region: self.region,
+            rasterizer: self.rasterizer,
             display: &mut self.display,
-            depths: vec![1.0; total_size],
-            colors: vec![Color::new(0.0, 0.0, 0.0); total_size]
+            depths: &mut self.depths,
+            colors: &mut self.colors,
+            coverage: &mut self.coverage,
+            pass: DrawPass::Full,
+            depth_func: DepthFunc::Less,
+            shaded: 0,
+            depth_of_field: self.depth_of_field
         }
     }
 }
@@ -67,8 +186,15 @@ impl<T: DrawableDisplay> DrawableNormal for &mut NormalDrawable<T>
 pub struct NormalSurface<'a, T>
 {
     size: (usize, usize),
-    depths: Vec<f64>,
-    colors: Vec<Color>,
+    region: Option<Region>,
+    rasterizer: Rasterizer,
+    depths: &'a mut [f64],
+    colors: &'a mut [Color],
+    coverage: &'a mut [bool],
+    pass: DrawPass,
+    depth_func: DepthFunc,
+    shaded: usize,
+    depth_of_field: Option<DepthOfField>,
     display: &'a mut T
 }
 
@@ -77,7 +203,42 @@ impl<'a, T: DrawableDisplay> DrawSurface<'a> for NormalSurface<'a, T>
     fn display(self)
     {
         self.display.prepare(self.size);
-        self.display.display(self.size, &self.colors);
+
+        match self.depth_of_field
+        {
+            Some(depth_of_field) =>
+            {
+                let colors = depth_of_field.blur(self.size, self.colors, self.depths, self.coverage);
+
+                self.display.display(self.size, &colors, self.coverage);
+            },
+            None => self.display.display(self.size, self.colors, self.coverage)
+        }
+    }
+
+    fn pixels_shaded(&self) -> usize
+    {
+        self.shaded
+    }
+
+    fn set_pass(&mut self, pass: DrawPass)
+    {
+        self.pass = pass;
+    }
+
+    fn uses_passes(&self) -> bool
+    {
+        true
+    }
+
+    fn set_depth_func(&mut self, depth_func: DepthFunc)
+    {
+        self.depth_func = depth_func;
+    }
+
+    fn depth_func(&self) -> DepthFunc
+    {
+        self.depth_func
     }
 }
 
@@ -86,24 +247,77 @@ impl<'a, T> Drawable<'a> for NormalSurface<'a, T>
 {
     fn set_pixel_data(&mut self, point: Point<usize>, shader: &'a FaceShader)
     {
-        let index = (self.size.1 - point.y - 1) * self.size.0 + point.x;
-
         let depth = point.get(ShaderValue::Depth);
-        if depth < -1.0 || depth > 1.0
-            || point.x >= self.size.0
-            || point.y >= self.size.1
+        if !(-1.0..=1.0).contains(&depth) || !inside(self.size, self.region, &point)
         {
             return;
         }
 
+        let index = (self.size.1 - point.y - 1) * self.size.0 + point.x;
+
         let pixel_depth = self.depths[index];
-        if depth < pixel_depth
+
+        let visible = match self.pass
+        {
+            DrawPass::Full => self.depth_func.passes(depth, pixel_depth),
+            DrawPass::DepthOnly =>
+            {
+                if self.depth_func.passes(depth, pixel_depth)
+                {
+                    self.depths[index] = depth;
+                }
+
+                return;
+            },
+            DrawPass::Shading if self.depth_func == DepthFunc::Always => true,
+            //the first triangle at the stored depth wins, same as a strict test in one pass
+            DrawPass::Shading => DepthFunc::Equal.passes(depth, pixel_depth) && !self.coverage[index]
+        };
+
+        if visible
         {
             let pixel_info = PixelInfo{interpolated: point.interpolated, shader: Some(shader)};
 
             self.colors[index] = color_shader::execute(&pixel_info);
             self.depths[index] = depth;
+            self.coverage[index] = true;
+
+            self.shaded += 1;
+        }
+    }
+
+    //blends with whatever is already there instead of replacing it
+    fn set_pixel_coverage(&mut self, point: Point<usize>, shader: &'a FaceShader, coverage: f64)
+    {
+        let depth = point.get(ShaderValue::Depth);
+        if !(-1.0..=1.0).contains(&depth) || !inside(self.size, self.region, &point)
+        {
+            return;
+        }
+
+        let index = (self.size.1 - point.y - 1) * self.size.0 + point.x;
+
+        //lines never write depth so the shading pass cant expect to find them there
+        let visible = match self.pass
+        {
+            DrawPass::Full => self.depth_func.passes(depth, self.depths[index]),
+            DrawPass::DepthOnly => false,
+            DrawPass::Shading => DepthFunc::LessEqual.passes(depth, self.depths[index])
+        };
+
+        if !visible
+        {
+            return;
         }
+
+        let pixel_info = PixelInfo{interpolated: point.interpolated, shader: Some(shader)};
+
+        let color = color_shader::execute(&pixel_info);
+
+        self.colors[index] = self.colors[index].lerp(&color, coverage.min(1.0));
+        self.coverage[index] = true;
+
+        self.shaded += 1;
     }
 
     fn to_local(&self, point: Point) -> Point<usize>
@@ -114,11 +328,31 @@ impl<'a, T> Drawable<'a> for NormalSurface<'a, T>
             interpolated: point.interpolated
         }
     }
+
+    fn size(&self) -> (usize, usize)
+    {
+        self.size
+    }
+
+    fn region(&self) -> Option<Region>
+    {
+        self.region
+    }
+
+    fn rasterizer(&self) -> Rasterizer
+    {
+        self.rasterizer
+    }
 }
 
 pub struct DeferredDrawable<T>
 {
     size: (usize, usize),
+    region: Option<Region>,
+    rasterizer: Rasterizer,
+    oit: bool,
+    depth_of_field: Option<DepthOfField>,
+    outline: Option<Outline>,
     display: T
 }
 
@@ -128,18 +362,247 @@ impl<T> DeferredDrawable<T>
     {
         Self{
             size,
+            region: None,
+            rasterizer: Rasterizer::Barycentric,
+            oit: false,
+            depth_of_field: None,
+            outline: None,
             display
         }
     }
+
+    //everything outside of the region is skipped, the frame stays the full size
+    pub fn set_region(&mut self, region: Option<Region>)
+    {
+        self.region = region;
+    }
+
+    pub fn set_rasterizer(&mut self, rasterizer: Rasterizer)
+    {
+        self.rasterizer = rasterizer;
+    }
+
+    pub fn set_outline(&mut self, outline: Option<Outline>)
+    {
+        self.outline = outline;
+    }
+
+    pub fn set_depth_of_field(&mut self, depth_of_field: Option<DepthOfField>)
+    {
+        self.depth_of_field = depth_of_field;
+    }
+
+    //see-through materials get blended instead of covering whatever is behind them
+    pub fn set_oit(&mut self, oit: bool)
+    {
+        self.oit = oit;
+    }
+
+    pub fn into_display(self) -> T
+    {
+        self.display
+    }
+}
+
+struct TransparentFragment
+{
+    index: usize,
+    depth: f64,
+    color: Color,
+    opacity: f64,
+    distance: f64
+}
+
+impl TransparentFragment
+{
+    //weighted blended oit from mcguire and bavoil, closer fragments count for more
+    fn weight(&self) -> f64
+    {
+        self.opacity * (0.03 / (0.00001 + (self.distance / 200.0).powi(4))).clamp(0.01, 3000.0)
+    }
 }
 
 pub struct DeferredSurface<'a, T>
 {
     size: (usize, usize),
+    region: Option<Region>,
+    rasterizer: Rasterizer,
     pixels: Vec<PixelInfo<'a>>,
+    ids: Vec<Option<PixelId>>,
+    oit: bool,
+    transparent: Vec<TransparentFragment>,
+    depth_func: DepthFunc,
+    depth_of_field: Option<DepthOfField>,
+    outline: Option<Outline>,
     display: &'a mut T
 }
 
+//the g-buffer can be read after drawing the geometry and before display shades it
+impl<'a, T> DeferredSurface<'a, T>
+{
+    //row major starting from the top row, empty pixels have no shader and a depth of 1
+    //index the interpolated values with ShaderValue
+    pub fn pixels(&self) -> &[PixelInfo<'a>]
+    {
+        &self.pixels
+    }
+
+    //y goes from the top down like in the final image
+    pub fn pixel(&self, x: usize, y: usize) -> Option<&PixelInfo<'a>>
+    {
+        if x >= self.size.0 || y >= self.size.1
+        {
+            return None;
+        }
+
+        Some(&self.pixels[y * self.size.0 + x])
+    }
+
+    //same layout as the pixels, empty pixels have no id
+    pub fn ids(&self) -> &[Option<PixelId>]
+    {
+        &self.ids
+    }
+}
+
+impl<'a, T> DeferredSurface<'a, T>
+{
+    //sobel over the distance and the normal, plus wherever the geometry ends
+    fn outline_edges(&self, object: Option<usize>) -> Vec<bool>
+    {
+        let (width, height) = self.size;
+
+        let values = self.pixels.iter().zip(self.ids.iter()).map(|(pixel, id)|
+        {
+            let masked = match (object, id)
+            {
+                (Some(object), Some(id)) => id.object != object,
+                _ => false
+            };
+
+            pixel.shader.filter(|_| !masked).map(|shader|
+            {
+                let position = Point3D{
+                    x: pixel.get(ShaderValue::PositionX),
+                    y: pixel.get(ShaderValue::PositionY),
+                    z: pixel.get(ShaderValue::PositionZ)
+                };
+
+                let normal = Point3D{
+                    x: pixel.get(ShaderValue::NormalX),
+                    y: pixel.get(ShaderValue::NormalY),
+                    z: pixel.get(ShaderValue::NormalZ)
+                }.normalized();
+
+                [position.distance(shader.camera_position), normal.x, normal.y, normal.z]
+            })
+        }).collect::<Vec<Option<[f64; 4]>>>();
+
+        let sample = |x: isize, y: isize|
+        {
+            values[y.clamp(0, height as isize - 1) as usize * width + x.clamp(0, width as isize - 1) as usize]
+        };
+
+        (0..width * height).map(|index|
+        {
+            let x = (index % width) as isize;
+            let y = (index / width) as isize;
+
+            let center = if let Some(center) = values[index]
+            {
+                center
+            } else
+            {
+                return false;
+            };
+
+            let mut gradient_x = [0.0; 4];
+            let mut gradient_y = [0.0; 4];
+
+            for (ox, oy, weight_x, weight_y) in [
+                (-1, -1, -1.0, -1.0), (0, -1, 0.0, -2.0), (1, -1, 1.0, -1.0),
+                (-1, 0, -2.0, 0.0), (1, 0, 2.0, 0.0),
+                (-1, 1, -1.0, 1.0), (0, 1, 0.0, 2.0), (1, 1, 1.0, 1.0)
+            ]
+            {
+                //next to the background is always an edge
+                let value = if let Some(value) = sample(x + ox, y + oy)
+                {
+                    value
+                } else
+                {
+                    return true;
+                };
+
+                for channel in 0..4
+                {
+                    gradient_x[channel] += value[channel] * weight_x;
+                    gradient_y[channel] += value[channel] * weight_y;
+                }
+            }
+
+            let magnitude = |channels: &[usize]|
+            {
+                channels.iter().map(|channel|
+                {
+                    gradient_x[*channel].powi(2) + gradient_y[*channel].powi(2)
+                }).sum::<f64>().sqrt()
+            };
+
+            //relative to the distance so far away things dont turn into solid outlines
+            let depth_edge = magnitude(&[0]) / center[0] > 0.5;
+            let normal_edge = magnitude(&[1, 2, 3]) > 1.0;
+
+            depth_edge || normal_edge
+        }).collect()
+    }
+
+    //opaque pixels can still end up in front after a transparent one was drawn,
+    //so the fragments only get summed up once all the depths are final
+    fn resolve_transparent(
+        pixels: &[PixelInfo],
+        transparent: &[TransparentFragment],
+        colors: &mut [Color],
+        coverage: &mut [bool]
+    )
+    {
+        let black = Color::new(0.0, 0.0, 0.0);
+
+        let mut accumulated = vec![(black, 0.0); colors.len()];
+        let mut revealage = vec![1.0; colors.len()];
+
+        let visible = transparent.iter().filter(|fragment|
+        {
+            fragment.depth < pixels[fragment.index].get(ShaderValue::Depth)
+        });
+
+        for fragment in visible
+        {
+            let weight = fragment.weight();
+
+            let (color, total) = &mut accumulated[fragment.index];
+            *color = *color + fragment.color * (fragment.opacity * weight);
+            *total += fragment.opacity * weight;
+
+            revealage[fragment.index] *= 1.0 - fragment.opacity;
+        }
+
+        let pixels = colors.iter_mut().zip(coverage.iter_mut()).zip(accumulated.into_iter().zip(revealage));
+        for ((color, covered), ((sum, total), revealage)) in pixels
+        {
+            if total <= 0.0
+            {
+                continue;
+            }
+
+            let average = sum * (1.0 / total);
+
+            *color = average.lerp(color, revealage);
+            *covered = true;
+        }
+    }
+}
+
 impl<T: DrawableDisplay> DrawableNormal for &mut DeferredDrawable<T>
 {
     type SurfaceType<'a> = DeferredSurface<'a, T> where Self: 'a;
@@ -153,8 +616,16 @@ impl<T: DrawableDisplay> DrawableNormal for &mut DeferredDrawable<T>
 
         DeferredSurface{
             size: self.size,
+            region: self.region,
+            rasterizer: self.rasterizer,
             display: &mut self.display,
-            pixels: vec![PixelInfo::new(empty); total_size]
+            pixels: vec![PixelInfo::new(empty); total_size],
+            ids: vec![None; total_size],
+            oit: self.oit,
+            transparent: Vec::new(),
+            depth_func: DepthFunc::Less,
+            depth_of_field: self.depth_of_field,
+            outline: self.outline
         }
     }
 }
@@ -165,8 +636,43 @@ impl<'a, T: DrawableDisplay> DrawSurface<'a> for DeferredSurface<'a, T>
     {
         self.display.prepare(self.size);
 
-        let colors = self.pixels.iter().map(color_shader::execute).collect::<Vec<Color>>();
-        self.display.display(self.size, &colors);
+        let mut colors = self.pixels.iter().map(color_shader::execute).collect::<Vec<Color>>();
+        let mut coverage = self.pixels.iter().map(|pixel| pixel.shader.is_some()).collect::<Vec<bool>>();
+
+        if !self.transparent.is_empty()
+        {
+            Self::resolve_transparent(&self.pixels, &self.transparent, &mut colors, &mut coverage);
+        }
+
+        if let Some(outline) = self.outline
+        {
+            outline.apply(self.size, &self.outline_edges(outline.object), &mut colors, &mut coverage);
+        }
+
+        if let Some(depth_of_field) = self.depth_of_field
+        {
+            let depths = self.pixels.iter().map(|pixel| pixel.get(ShaderValue::Depth)).collect::<Vec<f64>>();
+
+            colors = depth_of_field.blur(self.size, &colors, &depths, &coverage);
+        }
+
+        self.display.display(self.size, &colors, &coverage);
+    }
+
+    //every covered pixel gets shaded exactly once, transparent ones while drawing
+    fn pixels_shaded(&self) -> usize
+    {
+        self.pixels.iter().filter(|pixel| pixel.shader.is_some()).count() + self.transparent.len()
+    }
+
+    fn set_depth_func(&mut self, depth_func: DepthFunc)
+    {
+        self.depth_func = depth_func;
+    }
+
+    fn depth_func(&self) -> DepthFunc
+    {
+        self.depth_func
     }
 }
 
@@ -175,20 +681,40 @@ impl<'a, T> Drawable<'a> for DeferredSurface<'a, T>
 {
     fn set_pixel_data(&mut self, point: Point<usize>, shader: &'a FaceShader)
     {
+        let depth = point.get(ShaderValue::Depth);
+        if !(-1.0..=1.0).contains(&depth) || !inside(self.size, self.region, &point)
+        {
+            return;
+        }
+
         let index = (self.size.1 - point.y - 1) * self.size.0 + point.x;
 
-        let depth = point.get(ShaderValue::Depth);
-        if depth < -1.0 || depth > 1.0
-            || point.x >= self.size.0
-            || point.y >= self.size.1
+        if self.oit && shader.opacity < 1.0
         {
+            let pixel_info = PixelInfo{interpolated: point.interpolated, shader: Some(shader)};
+
+            let position = Point3D{
+                x: point.get(ShaderValue::PositionX),
+                y: point.get(ShaderValue::PositionY),
+                z: point.get(ShaderValue::PositionZ)
+            };
+
+            self.transparent.push(TransparentFragment{
+                index,
+                depth,
+                color: color_shader::execute(&pixel_info),
+                opacity: shader.opacity,
+                distance: position.distance(shader.camera_position)
+            });
+
             return;
         }
 
         let pixel_depth = self.pixels[index].get(ShaderValue::Depth);
-        if depth < pixel_depth
+        if self.depth_func.passes(depth, pixel_depth)
         {
             self.pixels[index].set(shader, point.interpolated);
+            self.ids[index] = Some(shader.id);
         }
     }
 
@@ -200,4 +726,41 @@ impl<'a, T> Drawable<'a> for DeferredSurface<'a, T>
             interpolated: point.interpolated
         }
     }
+
+    fn size(&self) -> (usize, usize)
+    {
+        self.size
+    }
+
+    fn region(&self) -> Option<Region>
+    {
+        self.region
+    }
+
+    fn rasterizer(&self) -> Rasterizer
+    {
+        self.rasterizer
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn depth_funcs()
+    {
+        //new depths in front of, on and behind the stored one
+        let passes = |depth_func: DepthFunc|
+        {
+            [-0.5, 0.0, 0.5].map(|depth| depth_func.passes(depth, 0.0))
+        };
+
+        assert_eq!(passes(DepthFunc::Less), [true, false, false]);
+        assert_eq!(passes(DepthFunc::LessEqual), [true, true, false]);
+        assert_eq!(passes(DepthFunc::Equal), [false, true, false]);
+        assert_eq!(passes(DepthFunc::Greater), [false, false, true]);
+        assert_eq!(passes(DepthFunc::Always), [true, true, true]);
+    }
 }
\ No newline at end of file
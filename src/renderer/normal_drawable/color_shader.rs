@@ -2,8 +2,11 @@ use crate::renderer::common::{
     Point2D,
     Point3D,
     Color,
+    DEFAULT_SHININESS,
+    Mat3x3,
     ShaderValue,
-    PixelInfo
+    PixelInfo,
+    Visualization
 };
 
 
@@ -11,7 +14,16 @@ pub fn execute(pixel: &PixelInfo) -> Color
 {
     if let Some(shader) = pixel.shader
     {
-        let shininess = 32;
+        let vertex_color = Color::new(
+            pixel.get(ShaderValue::ColorR),
+            pixel.get(ShaderValue::ColorG),
+            pixel.get(ShaderValue::ColorB)
+        );
+
+        if shader.unlit
+        {
+            return shader.color * vertex_color;
+        }
 
         let world_point = Point3D{
             x: pixel.get(ShaderValue::PositionX),
@@ -25,40 +37,147 @@ pub fn execute(pixel: &PixelInfo) -> Color
             z: pixel.get(ShaderValue::NormalZ)
         };
 
-        let object_color = if let Some(texture) = shader.texture
+        if let Some(Visualization::Normals) = shader.settings.visualization
+        {
+            let normal = normal.normalized();
+
+            return Color::new(
+                normal.x * 0.5 + 0.5,
+                normal.y * 0.5 + 0.5,
+                normal.z * 0.5 + 0.5
+            );
+        }
+
+        let camera_direction = (shader.camera_position - world_point).normalized();
+
+        //back faces would get lit from behind otherwise
+        let normal = if shader.double_sided && normal.dot(camera_direction) < 0.0
+        {
+            -normal
+        } else
+        {
+            normal
+        };
+
+        if let Some(matcap) = &shader.settings.matcap
         {
-            let uv = Point2D{
-                x: pixel.get(ShaderValue::UvX),
-                y: pixel.get(ShaderValue::UvY)
-            };
+            //only the rotation, the normal doesnt care where the camera is
+            let view_normal = Mat3x3::from(shader.camera_view) * [normal.x, normal.y, normal.z];
+            let view_normal = Point3D{x: view_normal[0], y: view_normal[1], z: view_normal[2]}.normalized();
 
+            return matcap.pixel(Point2D{x: view_normal.x * 0.5 + 0.5, y: view_normal.y * 0.5 + 0.5});
+        }
+
+        let uv = Point2D{
+            x: pixel.get(ShaderValue::UvX),
+            y: pixel.get(ShaderValue::UvY)
+        };
+
+        let object_color = if let Some(texture) = shader.texture
+        {
             texture.pixel(uv)
         } else
         {
             shader.color
         };
 
+        let object_color = object_color * vertex_color;
+
+        //a black gloss texel is completely rough, the exponent cant go below 1 though
+        let shininess = match shader.shininess_texture
+        {
+            Some(texture) => (shader.shininess * texture.pixel(uv).r).max(1.0),
+            None => shader.shininess.max(1.0)
+        };
+
+        let settings = shader.settings;
+
+        //the specular weight is the fraction of light reflected as highlights, and sharper
+        //highlights get brighter by the phong normalization since theyre spread over less area
+        let (diffuse_weight, specular_weight) = if settings.energy_conserving
+        {
+            let fraction = settings.specular.clamp(0.0, 1.0);
+            let normalization = (shininess + 2.0) / (DEFAULT_SHININESS + 2.0);
+
+            (settings.diffuse * (1.0 - fraction), fraction * normalization)
+        } else
+        {
+            (settings.diffuse, settings.specular)
+        };
+
         let mut brightness = 0.0;
 
         for light in shader.lights
         {
             let light_direction = (light.position - world_point).normalized();
+
+            let attenuation = light.attenuation(light_direction);
+            if attenuation <= 0.0
+            {
+                continue;
+            }
+
             let diffuse = normal.dot(light_direction).max(0.0);
 
-            //camera is always at 0 0 0 for me
-            let camera_direction = (-world_point).normalized();
             let reflect_direction = -light_direction.reflect(normal);
 
-            let specular = camera_direction.dot(reflect_direction).max(0.0).powi(shininess);
+            let specular = camera_direction.dot(reflect_direction).max(0.0).powf(shininess);
 
-            brightness += (diffuse + specular) * light.intensity;
+            brightness += (diffuse * diffuse_weight + specular * specular_weight) * light.intensity * attenuation;
         }
 
-        let ambient = 0.2;
+        let ambient = shader.settings.ambient;
         let brightness = ambient + brightness;
 
-        let darkened = Color::new(0.0, 0.0, 0.0).lerp(&object_color, (brightness + 0.3).min(1.0));
-        darkened.lerp(&Color::new(1.0, 1.0, 1.0), (brightness - 0.3).max(0.0))
+        //rounding up keeps the dimmest lit parts from going completely dark
+        let brightness = match shader.settings.cel_bands
+        {
+            Some(bands) => (brightness * bands as f64).ceil() / bands as f64,
+            None => brightness
+        };
+
+        //the ambient also lifts the shadows, without it unlit faces are fully black
+        let lifted = brightness + ambient * 1.5;
+
+        let darkened = Color::new(0.0, 0.0, 0.0).lerp(&object_color, lifted.min(1.0));
+        let color = darkened.lerp(&Color::new(1.0, 1.0, 1.0), (brightness - 0.3).max(0.0));
+
+        let color = match &shader.settings.environment
+        {
+            Some(environment) if shader.reflectivity > 0.0 =>
+            {
+                let reflect_direction = (-camera_direction).reflect(normal.normalized());
+
+                color.lerp(&environment.sample_direction(reflect_direction), shader.reflectivity)
+            },
+            _ => color
+        };
+
+        //emission doesnt care about any lights
+        let emission = match (shader.emissive, shader.emissive_texture)
+        {
+            (Some(emissive), Some(texture)) => Some(emissive * texture.pixel(uv)),
+            (None, Some(texture)) => Some(texture.pixel(uv)),
+            (emissive, None) => emissive
+        };
+
+        let color = match emission
+        {
+            Some(emission) => color + emission,
+            None => color
+        };
+
+        let color = match shader.settings.tonemap
+        {
+            Some(tonemap) => tonemap.apply(color),
+            None => color
+        };
+
+        match shader.settings.fog
+        {
+            Some(fog) => fog.apply(color, world_point.distance(shader.camera_position)),
+            None => color
+        }
     } else
     {
         Color::new(0.0, 0.0, 0.0)
@@ -1,6 +1,7 @@
 use crate::renderer::common::{
     Point,
     FaceShader,
+    Region,
     combine_interpolated,
     Interpolator,
     Interpolated,
@@ -8,6 +9,15 @@ use crate::renderer::common::{
 };
 
 
+//scanline is the old fill that traces the edges and fills between them, its kept around to compare
+//against since it doesnt follow any fill rule (shared edges get drawn twice)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rasterizer
+{
+    Scanline,
+    Barycentric
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct PointDesc
 {
@@ -22,39 +32,265 @@ pub struct Limits
     upper: PointDesc
 }
 
+const OUTSIDE_LEFT: u8 = 1;
+const OUTSIDE_RIGHT: u8 = 2;
+const OUTSIDE_BOTTOM: u8 = 4;
+const OUTSIDE_TOP: u8 = 8;
+
+//which sides of the 0 to 1 screen square the point is past
+fn outcode(point: &Point) -> u8
+{
+    let mut code = 0;
+
+    if point.x < 0.0
+    {
+        code |= OUTSIDE_LEFT;
+    } else if point.x > 1.0
+    {
+        code |= OUTSIDE_RIGHT;
+    }
+
+    if point.y < 0.0
+    {
+        code |= OUTSIDE_BOTTOM;
+    } else if point.y > 1.0
+    {
+        code |= OUTSIDE_TOP;
+    }
+
+    code
+}
+
+fn lerp_point(p0: &Point, p1: &Point, a: f64) -> Point
+{
+    let mut interpolated = INTERPOLATED_ZEROS;
+    for (value, (v0, v1)) in interpolated.iter_mut().zip(p0.interpolated.iter().zip(p1.interpolated.iter()))
+    {
+        *value = v0 * (1.0 - a) + v1 * a;
+    }
+
+    Point{
+        x: p0.x * (1.0 - a) + p1.x * a,
+        y: p0.y * (1.0 - a) + p1.y * a,
+        interpolated
+    }
+}
+
+//cohen sutherland, keeps far off screen points from turning into huge pixel loops
+fn clip_line(mut p0: Point, mut p1: Point) -> Option<(Point, Point)>
+{
+    let finite = |point: &Point| point.x.is_finite() && point.y.is_finite();
+    if !finite(&p0) || !finite(&p1)
+    {
+        return None;
+    }
+
+    let mut code0 = outcode(&p0);
+    let mut code1 = outcode(&p1);
+
+    loop
+    {
+        if (code0 | code1) == 0
+        {
+            return Some((p0, p1));
+        }
+
+        if (code0 & code1) != 0
+        {
+            return None;
+        }
+
+        let outside = if code0 != 0 {code0} else {code1};
+
+        let a = if (outside & OUTSIDE_LEFT) != 0
+        {
+            (0.0 - p0.x) / (p1.x - p0.x)
+        } else if (outside & OUTSIDE_RIGHT) != 0
+        {
+            (1.0 - p0.x) / (p1.x - p0.x)
+        } else if (outside & OUTSIDE_BOTTOM) != 0
+        {
+            (0.0 - p0.y) / (p1.y - p0.y)
+        } else
+        {
+            (1.0 - p0.y) / (p1.y - p0.y)
+        };
+
+        let mut clipped = lerp_point(&p0, &p1, a);
+
+        //snap to the edge so rounding doesnt leave it a hair outside forever
+        if (outside & OUTSIDE_LEFT) != 0
+        {
+            clipped.x = 0.0;
+        } else if (outside & OUTSIDE_RIGHT) != 0
+        {
+            clipped.x = 1.0;
+        } else if (outside & OUTSIDE_BOTTOM) != 0
+        {
+            clipped.y = 0.0;
+        } else
+        {
+            clipped.y = 1.0;
+        }
+
+        if outside == code0
+        {
+            p0 = clipped;
+            code0 = outcode(&p0);
+        } else
+        {
+            p1 = clipped;
+            code1 = outcode(&p1);
+        }
+    }
+}
+
+#[allow(dead_code)]
 pub trait Drawable<'a>
 {
     fn set_pixel_data(&mut self, point: Point<usize>, shader: &'a FaceShader);
     fn to_local(&self, point: Point) -> Point<usize>;
+    fn size(&self) -> (usize, usize);
+
+    //only pixels in here get drawn, everything else is left empty
+    fn region(&self) -> Option<Region>
+    {
+        None
+    }
+
+    fn rasterizer(&self) -> Rasterizer
+    {
+        Rasterizer::Barycentric
+    }
+
+    //surfaces that cant blend just draw the pixels that are mostly covered
+    fn set_pixel_coverage(&mut self, point: Point<usize>, shader: &'a FaceShader, coverage: f64)
+    {
+        if coverage >= 0.5
+        {
+            self.set_pixel_data(point, shader);
+        }
+    }
 
     fn line(&mut self, p0: Point, p1: Point, shader: &'a FaceShader)
     {
+        let (p0, p1) = if let Some(points) = clip_line(p0, p1)
+        {
+            points
+        } else
+        {
+            return;
+        };
+
         Self::line_points(self.to_local(p0), self.to_local(p1), |point|
         {
             self.set_pixel_data(point, shader)
         });
     }
 
-    fn line_pixel(
-        point: Point<usize>,
-        y_begin: usize,
-        y_points: &mut [Limits]
-    )
+    //xiaolin wu, every column gets split between the 2 pixels closest to the line
+    fn line_smooth(&mut self, p0: Point, p1: Point, shader: &'a FaceShader)
     {
-        if y_points.len() != 0
+        let (p0, p1) = if let Some(points) = clip_line(p0, p1)
+        {
+            points
+        } else
+        {
+            return;
+        };
+
+        let (width, height) = self.size();
+
+        //pixel centers end up on whole numbers
+        let to_pixels = |point: &Point| (point.x * width as f64 - 0.5, point.y * height as f64 - 0.5);
+
+        let (mut x0, mut y0) = to_pixels(&p0);
+        let (mut x1, mut y1) = to_pixels(&p1);
+
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep
+        {
+            (x0, y0) = (y0, x0);
+            (x1, y1) = (y1, x1);
+        }
+
+        let (p0, p1) = if x0 > x1
+        {
+            (x0, x1) = (x1, x0);
+            (y0, y1) = (y1, y0);
+
+            (p1, p0)
+        } else
         {
-            let limits = y_points.get_mut(point.y - y_begin).unwrap();
+            (p0, p1)
+        };
 
-            let desc = PointDesc{limit: point.x, interpolated: point.interpolated};
-            if limits.lower.limit > point.x
+        let dx = x1 - x0;
+        let gradient = if dx == 0.0
+        {
+            1.0
+        } else
+        {
+            (y1 - y0) / dx
+        };
+
+        let start = x0.round();
+        let end = x1.round();
+
+        let mut plot = |x: f64, y: f64, coverage: f64, interpolated|
+        {
+            let (x, y) = if steep
             {
-                limits.lower = desc;
-            }
+                (y, x)
+            } else
+            {
+                (x, y)
+            };
 
-            if limits.upper.limit < point.x
+            if x < 0.0 || y < 0.0 || coverage <= 0.0
             {
-                limits.upper = desc;
+                return;
             }
+
+            self.set_pixel_coverage(Point{x: x as usize, y: y as usize, interpolated}, shader, coverage);
+        };
+
+        let mut x = start;
+        while x <= end
+        {
+            let amount = if dx == 0.0
+            {
+                0.0
+            } else
+            {
+                ((x - x0) / dx).clamp(0.0, 1.0)
+            };
+
+            let interpolated = lerp_point(&p0, &p1, amount).interpolated;
+
+            let y = y0 + gradient * (x - x0);
+            let y_pixel = y.floor();
+            let fraction = y - y_pixel;
+
+            //the ends only cover part of their pixel
+            let gap = if start == end
+            {
+                (x1 - x0).clamp(0.0, 1.0)
+            } else if x == start
+            {
+                1.0 - (x0 + 0.5).fract()
+            } else if x == end
+            {
+                (x1 + 0.5).fract()
+            } else
+            {
+                1.0
+            };
+
+            plot(x, y_pixel, (1.0 - fraction) * gap, interpolated);
+            plot(x, y_pixel + 1.0, fraction * gap, interpolated);
+
+            x += 1.0;
         }
     }
 
@@ -155,7 +391,7 @@ pub trait Drawable<'a>
         mut draw_function: impl FnMut(Point<usize>)
     )
     {
-        let mut interpolator = interpolator.interpolator(length);
+        let mut interpolator = interpolator.interpolator(length - 1);
         for y in y..y+length
         {
             draw_function(Point{x, y, interpolated: interpolator.next().expect("infinite")});
@@ -170,7 +406,7 @@ pub trait Drawable<'a>
         mut draw_function: impl FnMut(Point<usize>)
     )
     {
-        let mut interpolator = interpolator.interpolator(length);
+        let mut interpolator = interpolator.interpolator(length - 1);
         for x in x..x+length
         {
             draw_function(Point{x, y, interpolated: interpolator.next().expect("infinite")});
@@ -184,8 +420,8 @@ pub trait Drawable<'a>
     )
     {
         let values = combine_interpolated(p0.interpolated, p1.interpolated);
-        let y_abs_diff = (p1.y as i32 - p0.y as i32).abs() as usize;
-        let x_abs_diff = (p1.x as i32 - p0.x as i32).abs() as usize;
+        let y_abs_diff = (p1.y as i32 - p0.y as i32).unsigned_abs() as usize;
+        let x_abs_diff = (p1.x as i32 - p0.x as i32).unsigned_abs() as usize;
 
         if p0.x == p1.x
         {
@@ -262,6 +498,49 @@ pub trait Drawable<'a>
         }
     }
 
+    //rows outside of the screen arent kept track of, only the ones that can get drawn
+    fn line_pixel(
+        point: Point<usize>,
+        y_begin: usize,
+        y_points: &mut [Limits]
+    )
+    {
+        let limits = if let Some(limits) = point.y.checked_sub(y_begin).and_then(|y| y_points.get_mut(y))
+        {
+            limits
+        } else
+        {
+            return;
+        };
+
+        let desc = PointDesc{limit: point.x, interpolated: point.interpolated};
+        if limits.lower.limit > point.x
+        {
+            limits.lower = desc;
+        }
+
+        if limits.upper.limit < point.x
+        {
+            limits.upper = desc;
+        }
+    }
+
+    //twice the signed area of a b p, positive when p is to the left of a to b
+    fn edge_function(a: (f64, f64), b: (f64, f64), p: (f64, f64)) -> f64
+    {
+        (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+    }
+
+    //pixels exactly on an edge only belong to the triangle if its a top or left edge,
+    //the neighbor sharing it walks it the other way so exactly one of them gets the pixel
+    fn top_left(a: (f64, f64), b: (f64, f64)) -> bool
+    {
+        let dx = b.0 - a.0;
+        let dy = b.1 - a.1;
+
+        dy > 0.0 || (dy == 0.0 && dx < 0.0)
+    }
+
     fn triangle(
         &mut self,
         o0: Point,
@@ -270,12 +549,39 @@ pub trait Drawable<'a>
         shader: &'a FaceShader
     )
     {
+        match self.rasterizer()
+        {
+            Rasterizer::Scanline => self.triangle_scanline(o0, o1, o2, shader),
+            Rasterizer::Barycentric => self.triangle_barycentric(o0, o1, o2, shader)
+        }
+    }
+
+    //walks the edges to find where every row starts and ends, then fills the rows in between
+    fn triangle_scanline(
+        &mut self,
+        o0: Point,
+        o1: Point,
+        o2: Point,
+        shader: &'a FaceShader
+    )
+    {
+        let size = self.size();
+        if size.0 == 0 || size.1 == 0
+        {
+            return;
+        }
+
         let p0 = self.to_local(o0);
         let p1 = self.to_local(o1);
         let p2 = self.to_local(o2);
 
         let min_y = p0.y.min(p1.y.min(p2.y));
-        let max_y = p0.y.max(p1.y.max(p2.y));
+        let max_y = p0.y.max(p1.y.max(p2.y)).min(size.1 - 1);
+
+        if min_y > max_y
+        {
+            return;
+        }
 
         let mut points_slice = vec![Limits{
             lower: PointDesc{limit: usize::MAX, interpolated: INTERPOLATED_ZEROS},
@@ -290,6 +596,12 @@ pub trait Drawable<'a>
 
         for (index, limit) in points_slice.into_iter().enumerate()
         {
+            //rows the edges never reached
+            if limit.lower.limit > limit.upper.limit
+            {
+                continue;
+            }
+
             let y = min_y + index;
 
             let line_length = limit.upper.limit - limit.lower.limit;
@@ -298,11 +610,104 @@ pub trait Drawable<'a>
             Self::line_horizontal(
                 y, limit.lower.limit, line_length + 1,
                 Interpolator::new(values),
-                |point| {self.set_pixel_data(point, &shader)}
+                |point| {self.set_pixel_data(point, shader)}
             );
         }
     }
 
+    //tests every pixel center in the bounding box against the 3 edges
+    fn triangle_barycentric(
+        &mut self,
+        o0: Point,
+        o1: Point,
+        o2: Point,
+        shader: &'a FaceShader
+    )
+    {
+        let size = self.size();
+        if size.0 == 0 || size.1 == 0
+        {
+            return;
+        }
+
+        let to_screen = |point: &Point| (point.x * size.0 as f64, point.y * size.1 as f64);
+
+        let (mut o1, mut o2) = (o1, o2);
+        let v0 = to_screen(&o0);
+        let (mut v1, mut v2) = (to_screen(&o1), to_screen(&o2));
+
+        let mut area = Self::edge_function(v0, v1, v2);
+        if !area.is_finite() || area == 0.0
+        {
+            return;
+        }
+
+        //the fill rule needs the same winding for every triangle
+        if area < 0.0
+        {
+            (o1, o2) = (o2, o1);
+            (v1, v2) = (v2, v1);
+            area = -area;
+        }
+
+        //the region counts rows from the top, these go up from the bottom
+        let (low_x, low_y, high_x, high_y) = match self.region()
+        {
+            Some(region) => (
+                region.x0 as f64,
+                size.1.saturating_sub(region.y1) as f64,
+                region.x1 as f64 - 1.0,
+                size.1 as f64 - 1.0 - region.y0 as f64
+            ),
+            None => (0.0, 0.0, size.0 as f64 - 1.0, size.1 as f64 - 1.0)
+        };
+
+        let min_x = v0.0.min(v1.0).min(v2.0).floor().max(low_x);
+        let min_y = v0.1.min(v1.1).min(v2.1).floor().max(low_y);
+        let max_x = v0.0.max(v1.0).max(v2.0).ceil().min(high_x);
+        let max_y = v0.1.max(v1.1).max(v2.1).ceil().min(high_y);
+
+        if min_x > max_x || min_y > max_y
+        {
+            return;
+        }
+
+        let edges = [(v1, v2), (v2, v0), (v0, v1)];
+        let top_left = edges.map(|(a, b)| Self::top_left(a, b));
+
+        for y in (min_y as usize)..=(max_y as usize)
+        {
+            for x in (min_x as usize)..=(max_x as usize)
+            {
+                let center = (x as f64 + 0.5, y as f64 + 0.5);
+
+                let weights = edges.map(|(a, b)| Self::edge_function(a, b, center));
+
+                let inside = weights.iter().zip(top_left.iter()).all(|(weight, top_left)|
+                {
+                    *weight > 0.0 || (*weight == 0.0 && *top_left)
+                });
+
+                if !inside
+                {
+                    continue;
+                }
+
+                let (l0, l1, l2) = (weights[0] / area, weights[1] / area, weights[2] / area);
+
+                let mut interpolated = INTERPOLATED_ZEROS;
+                for (index, value) in interpolated.iter_mut().enumerate()
+                {
+                    *value = o0.interpolated[index] * l0
+                        + o1.interpolated[index] * l1
+                        + o2.interpolated[index] * l2;
+                }
+
+                self.set_pixel_data(Point{x, y, interpolated}, shader);
+            }
+        }
+    }
+
     fn triangle_wireframe(
         &mut self,
         p0: Point,
@@ -311,8 +716,234 @@ pub trait Drawable<'a>
         shader: &'a FaceShader
     )
     {
-        self.line(p0, p1, &shader);
-        self.line(p1, p2, &shader);
-        self.line(p2, p0, &shader);
+        self.line(p0, p1, shader);
+        self.line(p1, p2, shader);
+        self.line(p2, p0, shader);
+    }
+
+    fn triangle_wireframe_smooth(
+        &mut self,
+        p0: Point,
+        p1: Point,
+        p2: Point,
+        shader: &'a FaceShader
+    )
+    {
+        self.line_smooth(p0, p1, shader);
+        self.line_smooth(p1, p2, shader);
+        self.line_smooth(p2, p0, shader);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use crate::renderer::common::{Color, ShaderSettings};
+
+    //counts how many times every pixel got drawn and how much smooth lines covered it
+    struct Grid
+    {
+        size: (usize, usize),
+        counts: Vec<usize>,
+        coverage: Vec<f64>
+    }
+
+    impl Grid
+    {
+        fn new(size: (usize, usize)) -> Self
+        {
+            Self{size, counts: vec![0; size.0 * size.1], coverage: vec![0.0; size.0 * size.1]}
+        }
+
+        fn coverage(&self, x: usize, y: usize) -> f64
+        {
+            self.coverage[y * self.size.0 + x]
+        }
+    }
+
+    impl<'a> Drawable<'a> for Grid
+    {
+        fn set_pixel_data(&mut self, point: Point<usize>, _shader: &'a FaceShader)
+        {
+            if point.x < self.size.0 && point.y < self.size.1
+            {
+                self.counts[point.y * self.size.0 + point.x] += 1;
+            }
+        }
+
+        fn set_pixel_coverage(&mut self, point: Point<usize>, _shader: &'a FaceShader, coverage: f64)
+        {
+            if point.x < self.size.0 && point.y < self.size.1
+            {
+                self.coverage[point.y * self.size.0 + point.x] += coverage;
+            }
+        }
+
+        fn to_local(&self, point: Point) -> Point<usize>
+        {
+            Point{
+                x: (point.x * self.size.0 as f64) as usize,
+                y: (point.y * self.size.1 as f64) as usize,
+                interpolated: point.interpolated
+            }
+        }
+
+        fn size(&self) -> (usize, usize)
+        {
+            self.size
+        }
+    }
+
+    //the second interpolated value follows x so clipping can be checked to lerp everything
+    fn point(x: f64, y: f64) -> Point
+    {
+        let mut interpolated = INTERPOLATED_ZEROS;
+        interpolated[1] = x;
+
+        Point{x, y, interpolated}
+    }
+
+    fn close_enough(a: f64, b: f64) -> bool
+    {
+        (a - b).abs() < 0.0001
+    }
+
+    #[test]
+    fn clip_line_inside()
+    {
+        let (p0, p1) = clip_line(point(0.2, 0.3), point(0.7, 0.9)).unwrap();
+
+        assert_eq!((p0.x, p0.y, p1.x, p1.y), (0.2, 0.3, 0.7, 0.9));
+    }
+
+    #[test]
+    fn clip_line_across()
+    {
+        let (p0, p1) = clip_line(point(-1.0, 0.5), point(2.0, 0.5)).unwrap();
+
+        assert_eq!((p0.x, p1.x), (0.0, 1.0));
+        assert!(close_enough(p0.y, 0.5) && close_enough(p1.y, 0.5));
+
+        assert!(close_enough(p0.interpolated[1], 0.0));
+        assert!(close_enough(p1.interpolated[1], 1.0));
+
+        //crosses the top left corner region and the bottom right one
+        let (p0, p1) = clip_line(point(-1.0, 2.0), point(2.0, -1.0)).unwrap();
+
+        assert!(close_enough(p0.x, 0.0) && close_enough(p0.y, 1.0));
+        assert!(close_enough(p1.x, 1.0) && close_enough(p1.y, 0.0));
+    }
+
+    #[test]
+    fn clip_line_outside()
+    {
+        //both on the same side
+        assert!(clip_line(point(-1.0, 0.2), point(-0.5, 0.8)).is_none());
+        assert!(clip_line(point(0.2, 1.5), point(0.8, 3.0)).is_none());
+
+        //different sides but the line misses the screen
+        assert!(clip_line(point(-0.5, 0.8), point(0.2, 1.5)).is_none());
+
+        assert!(clip_line(point(f64::NAN, 0.5), point(0.5, 0.5)).is_none());
+        assert!(clip_line(point(0.5, 0.5), point(f64::INFINITY, 0.5)).is_none());
+    }
+
+    #[test]
+    fn edge_function_sides()
+    {
+        let (a, b) = ((0.0, 0.0), (1.0, 0.0));
+
+        assert_eq!(Grid::edge_function(a, b, (0.5, 1.0)), 1.0);
+        assert_eq!(Grid::edge_function(a, b, (0.5, -2.0)), -2.0);
+        assert_eq!(Grid::edge_function(a, b, (3.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn shared_edges_fill_once()
+    {
+        let settings = ShaderSettings::default();
+        let shader = FaceShader::new(Color::new(1.0, 1.0, 1.0), &[], &settings);
+
+        let mut grid = Grid::new((8, 8));
+
+        //a fan of triangles around the middle with edges going exactly through pixel centers
+        let center = point(0.5625, 0.5625);
+        let corners = [point(0.0, 0.0), point(1.0, 0.0), point(1.0, 1.0), point(0.0, 1.0)];
+
+        for index in 0..corners.len()
+        {
+            let next = corners[(index + 1) % corners.len()];
+
+            grid.triangle(center, corners[index], next, &shader);
+        }
+
+        assert!(grid.counts.iter().all(|count| *count == 1), "{:?}", grid.counts);
+
+        //the winding doesnt change what gets covered
+        let mut flipped = Grid::new((8, 8));
+        for index in 0..corners.len()
+        {
+            let next = corners[(index + 1) % corners.len()];
+
+            flipped.triangle(center, next, corners[index], &shader);
+        }
+
+        assert_eq!(grid.counts, flipped.counts);
+    }
+
+    #[test]
+    fn degenerate_triangle_fill()
+    {
+        let settings = ShaderSettings::default();
+        let shader = FaceShader::new(Color::new(1.0, 1.0, 1.0), &[], &settings);
+
+        let mut grid = Grid::new((8, 8));
+
+        grid.triangle(point(0.1, 0.1), point(0.5, 0.5), point(0.9, 0.9), &shader);
+        grid.triangle(point(0.1, 0.1), point(f64::NAN, 0.5), point(0.9, 0.2), &shader);
+
+        //too small to reach any pixel center
+        grid.triangle(point(0.01, 0.01), point(0.02, 0.01), point(0.01, 0.02), &shader);
+
+        assert!(grid.counts.iter().all(|count| *count == 0));
+    }
+
+    #[test]
+    fn smooth_line_coverage()
+    {
+        let settings = ShaderSettings::default();
+        let shader = FaceShader::new(Color::new(1.0, 1.0, 1.0), &[], &settings);
+
+        //pixel centers sit at halves
+        let center = |value: f64| (value + 0.5) / 8.0;
+
+        //right through the middle of a row, the ends only cover half their pixel
+        let mut grid = Grid::new((8, 8));
+        grid.line_smooth(point(center(0.0), center(3.0)), point(center(7.0), center(3.0)), &shader);
+
+        let row = (0..8).map(|x| grid.coverage(x, 3)).collect::<Vec<_>>();
+        assert_eq!(row, [0.5, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.5]);
+        assert!((0..8).all(|x| grid.coverage(x, 4) == 0.0 && grid.coverage(x, 2) == 0.0));
+
+        //exactly between 2 rows splits every column evenly
+        let mut grid = Grid::new((8, 8));
+        grid.line_smooth(point(center(0.0), 0.5), point(center(7.0), 0.5), &shader);
+
+        assert!((1..7).all(|x| close_enough(grid.coverage(x, 3), 0.5) && close_enough(grid.coverage(x, 4), 0.5)));
+
+        //steep lines go row by row instead, every row still adds up to one full pixel
+        let mut grid = Grid::new((8, 8));
+        grid.line_smooth(point(center(2.0), center(0.0)), point(center(4.0), center(7.0)), &shader);
+
+        for y in 1..7
+        {
+            let total = (0..8).map(|x| grid.coverage(x, y)).sum::<f64>();
+            assert!(close_enough(total, 1.0), "{y} {total}");
+        }
+
+        //the plain fill never gets used for smooth lines
+        assert!(grid.counts.iter().all(|count| *count == 0));
     }
 }
\ No newline at end of file
@@ -1,20 +1,27 @@
-use image::{Rgb, ImageBuffer};
+use image::{Rgb, Rgba, ImageBuffer};
 
-use crate::renderer::common::Color;
+use crate::renderer::common::{Color, Gamma};
 use crate::renderer::normal_drawable::DrawableDisplay;
 
 
 pub struct Picture
 {
-    filename: String
+    filename: String,
+    transparent: bool,
+    gamma: Gamma
 }
 
 #[allow(dead_code)]
 impl Picture
 {
-    pub fn new(filename: String) -> Self
+    pub fn new(filename: String, transparent: bool, gamma: Gamma) -> Self
     {
-        Picture{filename}
+        Picture{filename, transparent, gamma}
+    }
+
+    fn convert(&self, color: f64) -> u8
+    {
+        self.gamma.encode_u8(color)
     }
 }
 
@@ -22,17 +29,36 @@ impl Picture
 impl DrawableDisplay for Picture
 {
     fn prepare(&mut self, _: (usize, usize)) {}
-    fn display(&self, size: (usize, usize), colors: &[Color])
+    fn display(&mut self, size: (usize, usize), colors: &[Color], coverage: &[bool])
     {
-        let mut image = ImageBuffer::new(size.0 as u32, size.1 as u32);
+        if self.transparent
+        {
+            let mut image = ImageBuffer::new(size.0 as u32, size.1 as u32);
+
+            let pixels = image.pixels_mut().zip(colors.iter().zip(coverage.iter()));
+            for (pixel, (color, covered)) in pixels
+            {
+                let alpha = if *covered {u8::MAX} else {0};
 
-        for (pixel, color) in image.pixels_mut().zip(colors.iter())
+                *pixel = Rgba([
+                    self.convert(color.r),
+                    self.convert(color.g),
+                    self.convert(color.b),
+                    alpha
+                ]);
+            }
+
+            image.save(self.filename.clone()).unwrap();
+        } else
         {
-            let convert = |color| (color * 255.0) as u8;
+            let mut image = ImageBuffer::new(size.0 as u32, size.1 as u32);
 
-            *pixel = Rgb([convert(color.r), convert(color.g), convert(color.b)]);
-        }
+            for (pixel, color) in image.pixels_mut().zip(colors.iter())
+            {
+                *pixel = Rgb([self.convert(color.r), self.convert(color.g), self.convert(color.b)]);
+            }
 
-        image.save(self.filename.clone()).unwrap();
+            image.save(self.filename.clone()).unwrap();
+        }
     }
-}
\ No newline at end of file
+}
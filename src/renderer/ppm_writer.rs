@@ -0,0 +1,43 @@
+use std::io::{self, Write};
+
+use crate::renderer::common::{Color, Gamma};
+use crate::renderer::normal_drawable::DrawableDisplay;
+
+
+pub struct PpmWriter
+{
+    gamma: Gamma
+}
+
+#[allow(dead_code)]
+impl PpmWriter
+{
+    pub fn new(gamma: Gamma) -> Self
+    {
+        PpmWriter{gamma}
+    }
+
+    fn convert(&self, color: f64) -> u8
+    {
+        self.gamma.encode_u8(color)
+    }
+}
+
+impl DrawableDisplay for PpmWriter
+{
+    fn prepare(&mut self, _: (usize, usize)) {}
+    fn display(&mut self, size: (usize, usize), colors: &[Color], _: &[bool])
+    {
+        let mut bytes = format!("P6\n{} {}\n255\n", size.0, size.1).into_bytes();
+
+        bytes.extend(colors.iter().flat_map(|color|
+        {
+            [self.convert(color.r), self.convert(color.g), self.convert(color.b)]
+        }));
+
+        let mut stdout = io::stdout().lock();
+
+        stdout.write_all(&bytes).unwrap();
+        stdout.flush().unwrap();
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    fs,
+    io,
+    path::Path
+};
+
+use serde::Deserialize;
+
+use crate::renderer::{
+    Transform,
+    Camera,
+    Object,
+    common::{Point3D, Light, LightKind, ShaderSettings},
+    model::{Model, ModelError}
+};
+
+
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum SceneError
+{
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Model(String, ModelError),
+    ZeroAxis(String)
+}
+
+impl From<io::Error> for SceneError
+{
+    fn from(value: io::Error) -> Self
+    {
+        SceneError::Io(value)
+    }
+}
+
+impl From<toml::de::Error> for SceneError
+{
+    fn from(value: toml::de::Error) -> Self
+    {
+        SceneError::Parse(value)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDescription
+{
+    #[serde(default = "CameraDescription::default_fov")]
+    fov: f64,
+    #[serde(default = "CameraDescription::default_near")]
+    near: f64,
+    #[serde(default = "CameraDescription::default_far")]
+    far: f64,
+    position: Option<[f64; 3]>,
+    target: Option<[f64; 3]>,
+    #[serde(default = "CameraDescription::default_up")]
+    up: [f64; 3],
+    #[serde(default)]
+    roll: f64
+}
+
+impl CameraDescription
+{
+    fn default_fov() -> f64
+    {
+        60.0
+    }
+
+    fn default_near() -> f64
+    {
+        0.1
+    }
+
+    fn default_far() -> f64
+    {
+        100.0
+    }
+
+    fn default_up() -> [f64; 3]
+    {
+        [0.0, 1.0, 0.0]
+    }
+}
+
+impl Default for CameraDescription
+{
+    fn default() -> Self
+    {
+        CameraDescription{
+            fov: Self::default_fov(),
+            near: Self::default_near(),
+            far: Self::default_far(),
+            position: None,
+            target: None,
+            up: Self::default_up(),
+            roll: 0.0
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LightDescription
+{
+    position: [f64; 3],
+    intensity: f64,
+    direction: Option<[f64; 3]>,
+    #[serde(default)]
+    inner_angle: f64,
+    #[serde(default)]
+    outer_angle: f64
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ObjectDescription
+{
+    model: String,
+    #[serde(default)]
+    position: [f64; 3],
+    #[serde(default = "ObjectDescription::default_scale")]
+    scale: [f64; 3],
+    #[serde(default)]
+    rotation: f64,
+    #[serde(default = "ObjectDescription::default_axis")]
+    axis: [f64; 3]
+}
+
+impl ObjectDescription
+{
+    fn default_scale() -> [f64; 3]
+    {
+        [1.0, 1.0, 1.0]
+    }
+
+    fn default_axis() -> [f64; 3]
+    {
+        [0.0, 1.0, 0.0]
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDescription
+{
+    #[serde(default)]
+    camera: CameraDescription,
+    #[serde(default)]
+    lights: Vec<LightDescription>,
+    #[serde(default)]
+    objects: Vec<ObjectDescription>
+}
+
+pub struct SceneObject
+{
+    pub model: Model,
+    pub transform: Transform
+}
+
+pub struct Scene
+{
+    pub camera: Camera,
+    pub lights: Vec<Light>,
+    pub objects: Vec<SceneObject>
+}
+
+impl Scene
+{
+    pub fn load(path: &str, aspect: f64) -> Result<Self, SceneError>
+    {
+        let description: SceneDescription = toml::from_str(&fs::read_to_string(path)?)?;
+
+        //model paths are relative to the scene file
+        let parent_dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+
+        let mut camera = Camera::new(
+            description.camera.near,
+            description.camera.far,
+            description.camera.fov.to_radians(),
+            aspect
+        );
+
+        let to_point = |[x, y, z]: [f64; 3]| Point3D{x, y, z};
+
+        let roll = description.camera.roll.to_radians();
+
+        //without a position the camera stays at the origin looking down negative z
+        if let Some(position) = description.camera.position
+        {
+            let position = to_point(position);
+            let target = description.camera.target.map(to_point)
+                .unwrap_or(position - Point3D{x: 0.0, y: 0.0, z: 1.0});
+
+            camera.look_at(position, target, to_point(description.camera.up), roll);
+        } else if roll != 0.0
+        {
+            camera.roll(roll);
+        }
+
+        let lights = description.lights.into_iter().map(|light|
+        {
+            let kind = match light.direction
+            {
+                Some(direction) => LightKind::Spot{
+                    direction: to_point(direction),
+                    inner_angle: light.inner_angle.to_radians(),
+                    outer_angle: light.outer_angle.to_radians()
+                },
+                None => LightKind::Point
+            };
+
+            Light{position: to_point(light.position), intensity: light.intensity, kind}
+        }).collect();
+
+        let objects = description.objects.into_iter().map(|object|
+        {
+            let model_path = parent_dir.join(&object.model);
+            let model_path = model_path.to_string_lossy();
+
+            let model = Model::read(&model_path)
+                .map_err(|err| SceneError::Model(model_path.clone().into_owned(), err))?;
+
+            let [x, y, z] = object.position;
+            let [sx, sy, sz] = object.scale;
+            let [ax, ay, az] = object.axis;
+            if ax == 0.0 && ay == 0.0 && az == 0.0
+            {
+                return Err(SceneError::ZeroAxis(object.model));
+            }
+
+            let transform = Transform::new((x, y, z), (sx, sy, sz), object.rotation, (ax, ay, az));
+
+            Ok(SceneObject{model, transform})
+        }).collect::<Result<Vec<SceneObject>, SceneError>>()?;
+
+        Ok(Scene{camera, lights, objects})
+    }
+
+    pub fn objects<'a>(&'a self, settings: &'a ShaderSettings) -> Vec<Object<'a>>
+    {
+        self.objects.iter().map(|object|
+        {
+            Object::new(&object.model, object.transform.clone(), &self.camera, &self.lights, settings)
+        }).collect()
+    }
+}
@@ -0,0 +1,46 @@
+use std::{
+    fs,
+    path::{Path, PathBuf}
+};
+
+use crate::renderer::common::{Color, Gamma};
+use crate::renderer::picture::Picture;
+use crate::renderer::normal_drawable::DrawableDisplay;
+
+
+//every displayed frame becomes its own png, {n} in the pattern is the frame number
+pub struct SequenceWriter
+{
+    pattern: PathBuf,
+    frame: usize,
+    transparent: bool,
+    gamma: Gamma
+}
+
+#[allow(dead_code)]
+impl SequenceWriter
+{
+    pub fn new(pattern: PathBuf, transparent: bool, gamma: Gamma) -> Self
+    {
+        if let Some(directory) = pattern.parent().filter(|directory| *directory != Path::new(""))
+        {
+            fs::create_dir_all(directory).unwrap();
+        }
+
+        SequenceWriter{pattern, frame: 0, transparent, gamma}
+    }
+}
+
+impl DrawableDisplay for SequenceWriter
+{
+    fn prepare(&mut self, _: (usize, usize)) {}
+    fn display(&mut self, size: (usize, usize), colors: &[Color], coverage: &[bool])
+    {
+        let filename = self.pattern.to_string_lossy().replace("{n}", &format!("{:03}", self.frame));
+
+        let mut picture = Picture::new(filename, self.transparent, self.gamma);
+        picture.display(size, colors, coverage);
+
+        self.frame += 1;
+    }
+}
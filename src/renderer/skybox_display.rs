@@ -0,0 +1,102 @@
+use crate::renderer::{
+    Camera,
+    common::{Color, Point3D, Mat4x4, Texture, Tonemap},
+    normal_drawable::DrawableDisplay
+};
+
+
+pub struct Skybox
+{
+    texture: Texture,
+    inverse_projection: Mat4x4,
+    position: Point3D
+}
+
+impl Skybox
+{
+    pub fn new(texture: Texture, camera: &Camera) -> Self
+    {
+        let inverse_projection = (camera.matrix() * camera.view_matrix()).inverse();
+
+        Self{texture, inverse_projection, position: camera.position()}
+    }
+
+    //x and y go from -1 to 1 across the screen with y pointing up
+    pub fn view_ray(&self, x: f64, y: f64) -> Point3D
+    {
+        let point = self.inverse_projection * [x, y, 1.0, 1.0];
+
+        let point = Point3D{
+            x: point[0] / point[3],
+            y: point[1] / point[3],
+            z: point[2] / point[3]
+        };
+
+        (point - self.position).normalized()
+    }
+
+    pub fn sample(&self, direction: Point3D) -> Color
+    {
+        self.texture.sample_direction(direction)
+    }
+}
+
+//fills in every pixel no triangle covered before passing the frame along
+pub struct SkyboxDisplay<'a, D>
+{
+    display: D,
+    skybox: &'a Skybox,
+    tonemap: Option<Tonemap>
+}
+
+impl<'a, D> SkyboxDisplay<'a, D>
+{
+    pub fn new(display: D, skybox: &'a Skybox) -> Self
+    {
+        Self{display, skybox, tonemap: None}
+    }
+
+    //the sky doesnt go through the shaders so it has to be tonemapped here to match the geometry
+    pub fn set_tonemap(&mut self, tonemap: Option<Tonemap>)
+    {
+        self.tonemap = tonemap;
+    }
+}
+
+impl<'a, D: DrawableDisplay> DrawableDisplay for SkyboxDisplay<'a, D>
+{
+    fn prepare(&mut self, size: (usize, usize))
+    {
+        self.display.prepare(size);
+    }
+
+    fn display(&mut self, size: (usize, usize), colors: &[Color], coverage: &[bool])
+    {
+        let colors = colors.iter().zip(coverage.iter()).enumerate().map(|(index, (color, covered))|
+        {
+            if *covered
+            {
+                return *color;
+            }
+
+            let x = index % size.0;
+            let y = index / size.0;
+
+            //rows go from the top down
+            let x = (x as f64 + 0.5) / size.0 as f64 * 2.0 - 1.0;
+            let y = 1.0 - (y as f64 + 0.5) / size.1 as f64 * 2.0;
+
+            let color = self.skybox.sample(self.skybox.view_ray(x, y));
+
+            match self.tonemap
+            {
+                Some(tonemap) => tonemap.apply(color),
+                None => color
+            }
+        }).collect::<Vec<Color>>();
+
+        let coverage = vec![true; coverage.len()];
+
+        self.display.display(size, &colors, &coverage);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::renderer::common::Color;
+use crate::renderer::normal_drawable::DrawableDisplay;
+
+
+//stretches a smaller render to the output size by repeating the nearest pixel
+pub struct UpscaleDisplay<D>
+{
+    display: D,
+    size: (usize, usize)
+}
+
+impl<D> UpscaleDisplay<D>
+{
+    pub fn new(display: D, size: (usize, usize)) -> Self
+    {
+        Self{display, size}
+    }
+}
+
+impl<D: DrawableDisplay> DrawableDisplay for UpscaleDisplay<D>
+{
+    fn prepare(&mut self, _: (usize, usize))
+    {
+        self.display.prepare(self.size);
+    }
+
+    fn display(&mut self, size: (usize, usize), colors: &[Color], coverage: &[bool])
+    {
+        let (width, height) = self.size;
+
+        let indices = (0..width * height).map(|index|
+        {
+            let x = (index % width) * size.0 / width;
+            let y = (index / width) * size.1 / height;
+
+            y * size.0 + x
+        }).collect::<Vec<usize>>();
+
+        let colors = indices.iter().map(|index| colors[*index]).collect::<Vec<Color>>();
+        let coverage = indices.iter().map(|index| coverage[*index]).collect::<Vec<bool>>();
+
+        self.display.display(self.size, &colors, &coverage);
+    }
+}